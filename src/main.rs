@@ -1,32 +1,59 @@
-use ansi_term::Colour::{Blue, Fixed, Green, Red, White, Yellow};
-use anyhow::{Context, Result};
-use clap::Parser;
-use gix::bstr::{BStr, ByteSlice};
+use ansi_term::Colour::{self, Blue, Cyan, Fixed, Green, Purple, Red, Yellow};
+use ansi_term::Style;
+use anyhow::{anyhow, Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use gix::bstr::{BStr, BString, ByteSlice};
+use gix::status::plumbing::index_as_worktree::{Conflict, EntryStatus};
 use gix::{ObjectId, Repository};
-use std::collections::BTreeMap;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ffi::{OsStr, OsString};
 use std::fs::ReadDir;
-use std::path::{Component, Components, Path, PathBuf};
+use std::io::IsTerminal;
+use std::num::NonZeroUsize;
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 #[derive(Debug)]
 enum Node {
     Tree(Tree),
     Summary(Summary),
     Leaf(Leaf),
+    Failure(Failure),
 }
 
 #[derive(Debug)]
 struct Tree {
-    name: OsString,
-    children: BTreeMap<OsString, Node>,
+    name: BString,
+    children: BTreeMap<(BString, NodeKind), Node>,
+}
+
+/// Children are keyed by their kind as well as their name, so a file that has replaced a
+/// directory of the same name (or vice versa) is shown next to it instead of replacing it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum NodeKind {
+    Directory,
+    /// An untracked or ignored directory shown as a single entry.
+    CollapsedDirectory,
+    File,
 }
 
 #[derive(Debug)]
 struct Summary {
-    name: OsString,
+    name: BString,
     stats: DiffStat,
 }
 
+/// A repository or directory that could not be read.
+#[derive(Debug)]
+struct Failure {
+    name: BString,
+    message: String,
+}
+
 // TODO:
 // Either rename some variants to match `git2::Status` variants or add docs on how they are
 // related.
@@ -35,14 +62,16 @@ enum Status {
     WorktreeRemoved,
     WorktreeAdded,
     WorktreeModified,
+    WorktreeRenamed(Rewrite),
+    WorktreeCopied(Rewrite),
     IndexRemoved,
     IndexAdded,
     IndexModified,
+    IndexRenamed(Rewrite),
+    IndexCopied(Rewrite),
     TypeChange,
-    Renamed,
-    Copied,
     IntentToAdd,
-    Conflict,
+    Conflict(Conflict),
 
     // TODO:
     // Is this the correct name? This is currently used to reflect `item.summary(): Option<Status>
@@ -53,22 +82,51 @@ enum Status {
     Ignored,
 }
 
-impl From<gix::status::Item> for Status {
-    fn from(item: gix::status::Item) -> Self {
+/// The origin of a renamed or copied file.
+#[derive(Debug)]
+struct Rewrite {
+    /// The repository-relative path the file was renamed or copied from.
+    source: BString,
+    /// A value between 0.0 and 1.0, 1.0 meaning that the content is unchanged.
+    similarity: f32,
+}
+
+impl Status {
+    fn from_item(repo: &Repository, item: gix::status::Item) -> Result<Self> {
         use gix::diff::index::ChangeRef;
         use gix::status::index_worktree::iter::Summary;
+        use gix::status::index_worktree::Item;
+
+        let status = match item {
+            gix::status::Item::IndexWorktree(Item::Rewrite {
+                source, diff, copy, ..
+            }) => {
+                let rewrite = Rewrite {
+                    source: source.rela_path().to_owned(),
+                    // `diff` is `None` if source and destination are identical.
+                    similarity: diff.map_or(1.0, |diff| diff.similarity),
+                };
 
-        match item {
+                if copy {
+                    Self::WorktreeCopied(rewrite)
+                } else {
+                    Self::WorktreeRenamed(rewrite)
+                }
+            }
+            gix::status::Item::IndexWorktree(Item::Modification {
+                status: EntryStatus::Conflict { summary, .. },
+                ..
+            }) => Self::Conflict(summary),
             gix::status::Item::IndexWorktree(item) => match item.summary() {
                 Some(summary) => match summary {
                     Summary::Removed => Self::WorktreeRemoved,
                     Summary::Added => Self::WorktreeAdded,
                     Summary::Modified => Self::WorktreeModified,
                     Summary::TypeChange => Self::TypeChange,
-                    Summary::Renamed => Self::Renamed,
-                    Summary::Copied => Self::Copied,
+                    Summary::Renamed | Summary::Copied | Summary::Conflict => {
+                        unreachable!("rewrites and conflicts are handled above")
+                    }
                     Summary::IntentToAdd => Self::IntentToAdd,
-                    Summary::Conflict => Self::Conflict,
                 },
                 None => Self::Ignored,
             },
@@ -76,37 +134,316 @@ impl From<gix::status::Item> for Status {
                 ChangeRef::Addition { .. } => Self::IndexAdded,
                 ChangeRef::Deletion { .. } => Self::IndexRemoved,
                 ChangeRef::Modification { .. } => Self::IndexModified,
-                ChangeRef::Rewrite { .. } => Self::IndexModified,
+                ChangeRef::Rewrite {
+                    source_location,
+                    source_id,
+                    location,
+                    id,
+                    copy,
+                    ..
+                } => {
+                    // `gix` only pairs up binary files if their content is identical, which is
+                    // covered by the first case.
+                    let similarity = if source_id == id {
+                        1.0
+                    } else {
+                        diff_blobs(
+                            repo,
+                            None,
+                            Some(source_id.into_owned()),
+                            None,
+                            Some(id.into_owned()),
+                            &location,
+                        )?
+                        .map_or(1.0, |stats| stats.similarity)
+                    };
+
+                    let rewrite = Rewrite {
+                        source: source_location.into_owned(),
+                        similarity,
+                    };
+
+                    if copy {
+                        Self::IndexCopied(rewrite)
+                    } else {
+                        Self::IndexRenamed(rewrite)
+                    }
+                }
             },
+        };
+
+        Ok(status)
+    }
+
+    fn rewrite(&self) -> Option<&Rewrite> {
+        match self {
+            Status::WorktreeRenamed(rewrite)
+            | Status::WorktreeCopied(rewrite)
+            | Status::IndexRenamed(rewrite)
+            | Status::IndexCopied(rewrite) => Some(rewrite),
+            _ => None,
+        }
+    }
+}
+
+impl Status {
+    /// Whether this status describes a change between `HEAD` and the index (as opposed to a
+    /// change between the index and the working tree).
+    fn is_index(&self) -> bool {
+        matches!(
+            self,
+            Status::IndexModified
+                | Status::IndexAdded
+                | Status::IndexRemoved
+                | Status::IndexRenamed(_)
+                | Status::IndexCopied(_)
+        )
+    }
+
+    fn kind(&self) -> StatusFilter {
+        match self {
+            _ if self.is_index() => StatusFilter::Staged,
+            Status::WorktreeAdded => StatusFilter::Untracked,
+            Status::Conflict(_) => StatusFilter::Conflict,
+            Status::Ignored => StatusFilter::Ignored,
+            _ => StatusFilter::Unstaged,
+        }
+    }
+
+    /// The letter `git diff --diff-filter` uses for this kind of change, if any.
+    fn diff_filter_letter(&self) -> Option<u8> {
+        match self {
+            Status::WorktreeAdded | Status::IndexAdded | Status::IntentToAdd => Some(b'A'),
+            Status::WorktreeCopied(_) | Status::IndexCopied(_) => Some(b'C'),
+            Status::WorktreeRemoved | Status::IndexRemoved => Some(b'D'),
+            Status::WorktreeModified | Status::IndexModified => Some(b'M'),
+            Status::WorktreeRenamed(_) | Status::IndexRenamed(_) => Some(b'R'),
+            Status::TypeChange => Some(b'T'),
+            Status::Conflict(_) => Some(b'U'),
+            Status::Ignored => None,
+        }
+    }
+
+    fn to_json_name(&self) -> &'static str {
+        match self {
+            Status::WorktreeModified | Status::IndexModified => "modified",
+            Status::WorktreeAdded | Status::IndexAdded => "added",
+            Status::WorktreeRemoved | Status::IndexRemoved => "removed",
+            Status::WorktreeRenamed(_) | Status::IndexRenamed(_) => "renamed",
+            Status::WorktreeCopied(_) | Status::IndexCopied(_) => "copied",
+            Status::TypeChange => "type_change",
+            Status::IntentToAdd => "intent_to_add",
+            Status::Conflict(_) => "conflict",
+            Status::Ignored => "ignored",
+        }
+    }
+}
+
+/// The two letters `git status --short` shows for an unmerged path, the first one describing
+/// "our" side, the second one describing "their" side (D: deleted, A: added, U: modified).
+fn conflict_modifiers(conflict: Conflict) -> (&'static str, &'static str) {
+    match conflict {
+        Conflict::BothDeleted => ("D", "D"),
+        Conflict::AddedByUs => ("A", "U"),
+        Conflict::DeletedByThem => ("U", "D"),
+        Conflict::AddedByThem => ("U", "A"),
+        Conflict::DeletedByUs => ("D", "U"),
+        Conflict::BothAdded => ("A", "A"),
+        Conflict::BothModified => ("U", "U"),
+    }
+}
+
+fn conflict_to_json_name(conflict: Conflict) -> &'static str {
+    match conflict {
+        Conflict::BothDeleted => "both_deleted",
+        Conflict::AddedByUs => "added_by_us",
+        Conflict::DeletedByThem => "deleted_by_them",
+        Conflict::AddedByThem => "added_by_them",
+        Conflict::DeletedByUs => "deleted_by_us",
+        Conflict::BothAdded => "both_added",
+        Conflict::BothModified => "both_modified",
+    }
+}
+
+/// The status of a single path, corresponding to the `XY` columns of `git status --short`.
+///
+/// `gix` reports changes between `HEAD` and the index separately from changes between the index
+/// and the working tree, so a file that has been modified after being staged yields two items
+/// that are merged into one `LeafStatus`.
+#[derive(Debug, Default)]
+struct LeafStatus {
+    index: Option<Status>,
+    worktree: Option<Status>,
+}
+
+impl LeafStatus {
+    fn merge(&mut self, other: LeafStatus) {
+        self.index = other.index.or(self.index.take());
+        self.worktree = other.worktree.or(self.worktree.take());
+    }
+}
+
+impl From<Status> for LeafStatus {
+    fn from(status: Status) -> Self {
+        if status.is_index() {
+            LeafStatus {
+                index: Some(status),
+                worktree: None,
+            }
+        } else {
+            LeafStatus {
+                index: None,
+                worktree: Some(status),
+            }
         }
     }
 }
 
 #[derive(Debug)]
 struct Leaf {
-    name: OsString,
-    status: Status,
+    name: BString,
+    status: LeafStatus,
+    /// The number of lines added to and removed from the file, if known.
+    stats: Option<LineStat>,
+    /// The number of files in an untracked or ignored directory that is shown as a single entry.
+    collapsed_files: Option<usize>,
 }
 
-// TODO:
-// `DiffStat` should differentiate between staged and unstaged changes.
-#[derive(Debug)]
-struct DiffStat {
-    branch: OsString,
+impl Leaf {
+    /// Merges the other item `gix` reports for the same path. Staged and unstaged lines are added
+    /// up, the way the summary's total does.
+    fn merge(&mut self, other: Leaf) {
+        self.status.merge(other.status);
+
+        self.stats = match (self.stats.take(), other.stats) {
+            (Some(mut stats), Some(other)) => {
+                stats.add(&other);
+
+                Some(stats)
+            }
+            (stats, other) => stats.or(other),
+        };
+        self.collapsed_files = self.collapsed_files.or(other.collapsed_files);
+    }
+}
+
+#[derive(Debug, Default)]
+struct LineStat {
     files_changed: usize,
     insertions: usize,
     deletions: usize,
+    /// The number of changed files whose lines can't be counted because they are binary.
+    binary_files: usize,
 }
 
-fn calculate_stats(
+impl LineStat {
+    fn add(&mut self, other: &LineStat) {
+        self.files_changed += other.files_changed;
+        self.insertions += other.insertions;
+        self.deletions += other.deletions;
+        self.binary_files += other.binary_files;
+    }
+}
+
+/// The remote-tracking branch configured for the current branch, e.g. `origin/main`.
+#[derive(Debug)]
+struct Upstream {
+    name: BString,
+    /// The number of commits on the current branch that are not on `name`.
+    ahead: usize,
+    /// The number of commits on `name` that are not on the current branch.
+    behind: usize,
+}
+
+impl Upstream {
+    fn for_branch(repo: &gix::Repository, branch: &gix::refs::FullNameRef) -> Result<Option<Self>> {
+        let Some(tracking_name) = repo
+            .branch_remote_tracking_ref_name(branch, gix::remote::Direction::Fetch)
+            .transpose()?
+        else {
+            return Ok(None);
+        };
+
+        // The remote-tracking branch doesn't exist before the first fetch.
+        let Some(mut tracking_ref) = repo.try_find_reference(tracking_name.as_ref())? else {
+            return Ok(None);
+        };
+        let Some(head_id) = repo.head()?.id() else {
+            return Ok(None);
+        };
+
+        let head_id = head_id.detach();
+        let upstream_id = tracking_ref.peel_to_id()?.detach();
+
+        Ok(Some(Upstream {
+            name: tracking_name.shorten().to_owned(),
+            ahead: count_commits(repo, head_id, upstream_id)?,
+            behind: count_commits(repo, upstream_id, head_id)?,
+        }))
+    }
+}
+
+/// Counts the commits reachable from `tip` that are not reachable from `hidden`.
+fn count_commits(repo: &gix::Repository, tip: ObjectId, hidden: ObjectId) -> Result<usize> {
+    let mut count = 0;
+
+    for info in repo.rev_walk([tip]).with_hidden([hidden]).all()? {
+        info?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[derive(Debug)]
+struct DiffStat {
+    branch: BString,
+    upstream: Option<Upstream>,
+    /// Changes between `HEAD` and the index.
+    staged: LineStat,
+    /// Changes between the index and the working tree.
+    unstaged: LineStat,
+    /// The number of unmerged paths. These don't contribute to `unstaged`.
+    conflicts: usize,
+    /// The number of distinct paths in `staged` and `unstaged`.
+    files_changed: usize,
+}
+
+impl DiffStat {
+    /// Whether there is anything to commit, to merge, to push, or to pull.
+    fn has_changes(&self) -> bool {
+        let diverged = self
+            .upstream
+            .as_ref()
+            .is_some_and(|upstream| upstream.ahead > 0 || upstream.behind > 0);
+
+        self.files_changed > 0 || self.conflicts > 0 || diverged
+    }
+
+    fn total(&self) -> LineStat {
+        LineStat {
+            files_changed: self.files_changed,
+            insertions: self.staged.insertions + self.unstaged.insertions,
+            deletions: self.staged.deletions + self.unstaged.deletions,
+            binary_files: self.staged.binary_files + self.unstaged.binary_files,
+        }
+    }
+}
+
+/// Diffs two versions of the blob at `path`. Each version is read from the worktree below `root`
+/// if given, or from the object database otherwise. A missing `id` stands for an empty blob.
+///
+/// Returns `None` if either version is binary, as there are no lines to compare then.
+fn diff_blobs(
     repo: &gix::Repository,
     old_root: Option<PathBuf>,
     old_id: Option<ObjectId>,
     new_root: Option<PathBuf>,
     new_id: Option<ObjectId>,
     path: &BStr,
-    diff_stat: &mut DiffStat,
-) -> Result<()> {
+) -> Result<Option<gix::diff::blob::DiffLineStats>> {
+    use gix::diff::blob::platform::prepare_diff::Operation;
+
     let worktree_roots = gix::diff::blob::pipeline::WorktreeRoots { old_root, new_root };
 
     let mut resource_cache = repo.diff_resource_cache(
@@ -130,6 +467,11 @@ fn calculate_stats(
     )?;
 
     let outcome = resource_cache.prepare_diff()?;
+
+    if matches!(outcome.operation, Operation::SourceOrDestinationIsBinary) {
+        return Ok(None);
+    }
+
     let input = gix::diff::blob::InternedInput::new(
         outcome.old.data.as_slice().unwrap_or_default(),
         outcome.new.data.as_slice().unwrap_or_default(),
@@ -137,338 +479,1498 @@ fn calculate_stats(
 
     let diff = gix::diff::blob::Diff::compute(gix::diff::blob::Algorithm::Histogram, &input);
 
-    diff_stat.files_changed += 1;
-    diff_stat.insertions += diff.count_additions() as usize;
-    diff_stat.deletions += diff.count_removals() as usize;
+    // This is the same measure `gix` uses when tracking rewrites between the index and the
+    // worktree, so that similarities are comparable no matter where a rename was detected.
+    let removed_bytes: usize = diff
+        .hunks()
+        .map(|hunk| {
+            input.before[hunk.before.start as usize..hunk.before.end as usize]
+                .iter()
+                .map(|token| input.interner[*token].len())
+                .sum::<usize>()
+        })
+        .sum();
+    let old_len = outcome.old.data.as_slice().unwrap_or_default().len();
+    let new_len = outcome.new.data.as_slice().unwrap_or_default().len();
+    let similarity = if old_len.max(new_len) == 0 {
+        1.0
+    } else {
+        (old_len - removed_bytes) as f32 / old_len.max(new_len) as f32
+    };
+
+    Ok(Some(gix::diff::blob::DiffLineStats {
+        removals: diff.count_removals(),
+        insertions: diff.count_additions(),
+        before: input.before.len(),
+        after: input.after.len(),
+        similarity,
+    }))
+}
+
+fn calculate_stats(
+    repo: &gix::Repository,
+    old_root: Option<PathBuf>,
+    old_id: Option<ObjectId>,
+    new_root: Option<PathBuf>,
+    new_id: Option<ObjectId>,
+    path: &BStr,
+    line_stat: &mut LineStat,
+) -> Result<()> {
+    line_stat.files_changed += 1;
+
+    match diff_blobs(repo, old_root, old_id, new_root, new_id, path)? {
+        Some(stats) => {
+            line_stat.insertions += stats.insertions as usize;
+            line_stat.deletions += stats.removals as usize;
+        }
+        None => line_stat.binary_files += 1,
+    }
 
     Ok(())
 }
 
-impl TryFrom<&gix::Repository> for DiffStat {
-    type Error = anyhow::Error;
+impl DiffStat {
+    fn new(repo: &gix::Repository, args: &Args, threads: NonZeroUsize) -> Result<Self> {
+        let head_name = repo.head_name()?;
 
-    fn try_from(repo: &gix::Repository) -> std::result::Result<Self, Self::Error> {
-        let branch: OsString = match repo.head_name()? {
-            Some(name) => name
-                .shorten()
-                .to_os_str()
-                .to_owned()
-                .context("HEAD is not a direct reference")?
-                .into(),
+        let branch: BString = match &head_name {
+            Some(name) => name.shorten().to_owned(),
             None => "detached HEAD".into(),
         };
 
+        let upstream = match &head_name {
+            Some(name) => Upstream::for_branch(repo, name.as_ref())?,
+            None => None,
+        };
+
         let mut diff_stat = DiffStat {
             branch,
+            upstream,
+            staged: LineStat::default(),
+            unstaged: LineStat::default(),
+            conflicts: 0,
             files_changed: 0,
-            insertions: 0,
-            deletions: 0,
         };
 
-        let status = repo
-            .status(gix::progress::Discard)?
-            .untracked_files(gix::status::UntrackedFiles::None);
-        let iter = status.into_iter(None)?;
+        let status =
+            status(repo, args, threads)?.untracked_files(gix::status::UntrackedFiles::None);
+        let iter = status.into_iter(args.patterns())?;
+
+        // A file that has been modified after being staged counts once towards the total.
+        let mut changed_paths = BTreeSet::new();
 
         for item in iter {
             let item = item?;
 
-            // TODO:
-            // Check the implementation of `Diff::stats` to figure out what `git` does in each of
-            // the other cases. Add more tests in case some cases aren't covered yet.
-            match item {
-                gix::status::Item::IndexWorktree(item) => {
-                    // This yields changes that have not been staged yet.
-                    use gix::status::index_worktree::Item;
-
-                    match item {
-                        Item::Modification {
-                            entry, rela_path, ..
-                        } => {
-                            calculate_stats(
-                                repo,
-                                None,
-                                Some(entry.id),
-                                repo.workdir().map(ToOwned::to_owned),
-                                None,
-                                rela_path.as_ref(),
-                                &mut diff_stat,
-                            )?;
-                        }
-                        Item::DirectoryContents { .. } => {
-                            // TODO:
-                            // Double-check that this is what `git2` does.
-                            // Do nothing.
-                        }
-                        Item::Rewrite { .. } => {
-                            // TODO:
-                            // Double-check that this is what `git2` does.
-                            // Do nothing.
-                        }
-                    };
-                }
-                gix::status::Item::TreeIndex(change_ref) => {
-                    // This yields changes that have already been staged.
-                    use gix::diff::index::ChangeRef;
-
-                    match change_ref {
-                        ChangeRef::Addition { location, id, .. } => {
-                            calculate_stats(
-                                repo,
-                                None,
-                                None,
-                                None,
-                                Some(id.into_owned()),
-                                &location,
-                                &mut diff_stat,
-                            )?;
-                        }
-                        ChangeRef::Deletion { location, id, .. } => {
-                            calculate_stats(
-                                repo,
-                                None,
-                                Some(id.into_owned()),
-                                None,
-                                None,
-                                &location,
-                                &mut diff_stat,
-                            )?;
-                        }
-                        ChangeRef::Modification {
-                            location,
-                            previous_id,
-                            id,
-                            ..
-                        } => {
-                            calculate_stats(
-                                repo,
-                                None,
-                                Some(previous_id.into_owned()),
-                                None,
-                                Some(id.into_owned()),
-                                &location,
-                                &mut diff_stat,
-                            )?;
-                        }
-                        ChangeRef::Rewrite { .. } => {
-                            // TODO:
-                            // Double-check that this is what `git2` does.
-                            // Do nothing.
-                        }
-                    };
-                }
+            // Only pay for the rename similarity `Status::from_item` computes if it's needed.
+            if args.filters_status() && !args.shows(&Status::from_item(repo, item.clone())?) {
+                continue;
+            }
+
+            if let gix::status::Item::IndexWorktree(
+                gix::status::index_worktree::Item::Modification {
+                    status: EntryStatus::Conflict { .. },
+                    ..
+                },
+            ) = item
+            {
+                diff_stat.conflicts += 1;
+
+                continue;
+            }
+
+            // Changes between `HEAD` and the index have already been staged.
+            let line_stat = match item {
+                gix::status::Item::TreeIndex(_) => &mut diff_stat.staged,
+                gix::status::Item::IndexWorktree(_) => &mut diff_stat.unstaged,
             };
+
+            match item_stats(repo, &item)? {
+                Some(stats) => line_stat.add(&stats),
+                // Submodules have no lines, but they still count as a changed file.
+                None => line_stat.files_changed += 1,
+            }
+
+            changed_paths.insert(item.location().to_owned());
         }
 
+        diff_stat.files_changed = changed_paths.len();
+
         Ok(diff_stat)
     }
 }
 
+/// Counts the lines added and removed by a single change. Returns `None` for
+/// changes that have no lines of their own, i.e. conflicts, submodules, and untracked
+/// directories.
+fn item_stats(repo: &Repository, item: &gix::status::Item) -> Result<Option<LineStat>> {
+    use gix::diff::index::ChangeRef;
+    use gix::dir::entry::Kind;
+    use gix::status::index_worktree::Item;
+
+    let mut stats = LineStat::default();
+
+    match item {
+        gix::status::Item::IndexWorktree(Item::Modification {
+            status: EntryStatus::Conflict { .. },
+            ..
+        }) => return Ok(None),
+        gix::status::Item::IndexWorktree(Item::Modification { entry, .. })
+            if entry.mode.is_submodule() =>
+        {
+            return Ok(None)
+        }
+        gix::status::Item::IndexWorktree(Item::Modification {
+            entry, rela_path, ..
+        }) => {
+            calculate_stats(
+                repo,
+                None,
+                Some(entry.id),
+                repo.workdir().map(ToOwned::to_owned),
+                None,
+                rela_path.as_ref(),
+                &mut stats,
+            )?;
+        }
+        gix::status::Item::IndexWorktree(Item::DirectoryContents { entry, .. }) => {
+            if !matches!(entry.disk_kind, Some(Kind::File | Kind::Symlink)) {
+                return Ok(None);
+            }
+
+            calculate_stats(
+                repo,
+                None,
+                None,
+                repo.workdir().map(ToOwned::to_owned),
+                None,
+                entry.rela_path.as_ref(),
+                &mut stats,
+            )?;
+        }
+        gix::status::Item::IndexWorktree(Item::Rewrite { diff, .. }) => {
+            // The rewrite has already been diffed to determine its similarity. `diff` is `None`
+            // if source and destination are identical.
+            stats.files_changed = 1;
+
+            if let Some(diff) = diff {
+                stats.insertions = diff.insertions as usize;
+                stats.deletions = diff.removals as usize;
+            }
+        }
+        gix::status::Item::TreeIndex(change_ref) if change_ref.entry_mode().is_submodule() => {
+            return Ok(None)
+        }
+        gix::status::Item::TreeIndex(change_ref) => {
+            let (previous_id, id) = match change_ref {
+                ChangeRef::Addition { id, .. } => (None, Some(id.clone().into_owned())),
+                ChangeRef::Deletion { id, .. } => (Some(id.clone().into_owned()), None),
+                ChangeRef::Modification {
+                    previous_id, id, ..
+                } => (
+                    Some(previous_id.clone().into_owned()),
+                    Some(id.clone().into_owned()),
+                ),
+                ChangeRef::Rewrite { source_id, id, .. } => (
+                    Some(source_id.clone().into_owned()),
+                    Some(id.clone().into_owned()),
+                ),
+            };
+
+            calculate_stats(
+                repo,
+                None,
+                previous_id,
+                None,
+                id,
+                change_ref.location(),
+                &mut stats,
+            )?;
+        }
+    }
+
+    Ok(Some(stats))
+}
+
 impl Tree {
-    fn add_leaf_at_path(&mut self, leaf: Leaf, path: &mut Components<'_>) {
+    /// Adds a leaf for the repository-relative path `location`, e.g. `a/b/c.txt`.
+    fn add_leaf_at_location(
+        &mut self,
+        location: &BStr,
+        status: LeafStatus,
+        stats: Option<LineStat>,
+        collapsed_files: Option<usize>,
+    ) {
+        let mut components = location
+            .split_str("/")
+            .filter(|component| !component.is_empty())
+            .collect::<Vec<_>>();
+
+        if let Some(file_name) = components.pop() {
+            let leaf = Leaf {
+                name: file_name.into(),
+                status,
+                stats,
+                collapsed_files,
+            };
+
+            self.add_leaf_at_path(leaf, &mut components.into_iter());
+        }
+    }
+
+    fn add_leaf_at_path<'a>(&mut self, leaf: Leaf, path: &mut impl Iterator<Item = &'a [u8]>) {
         let name = leaf.name.clone();
 
         self.add_node_at_path(Node::Leaf(leaf), name, path);
     }
 
-    fn add_node_at_path(&mut self, node: Node, name: OsString, path: &mut Components<'_>) {
+    /// Adds `node` below the directories `path` consists of, creating them as needed. `path` holds
+    /// the raw components of a repository-relative path, so any name can be represented, whether
+    /// or not it is valid UTF-8 or a valid path on this platform.
+    fn add_node_at_path<'a>(
+        &mut self,
+        node: Node,
+        name: BString,
+        path: &mut impl Iterator<Item = &'a [u8]>,
+    ) {
         match path.next() {
-            Some(Component::Normal(dir)) => {
-                if let Some(dir) = dir.to_str() {
-                    let new_node = self.children.entry(dir.into()).or_insert(Node::Tree(Tree {
+            Some(dir) => {
+                let new_node = self
+                    .children
+                    .entry((dir.into(), NodeKind::Directory))
+                    .or_insert(Node::Tree(Tree {
                         name: dir.into(),
                         children: BTreeMap::new(),
                     }));
 
-                    if let &mut Node::Tree(ref mut new_node) = new_node {
-                        new_node.add_node_at_path(node, name, path)
-                    }
-                };
+                if let &mut Node::Tree(ref mut new_node) = new_node {
+                    new_node.add_node_at_path(node, name, path)
+                }
             }
 
-            Some(_) => unimplemented!(),
-
-            _ => {
+            None => {
                 self.add_node(node, name);
             }
         }
     }
 
-    fn add_node(&mut self, node: Node, name: OsString) {
-        self.children.insert(name, node);
-    }
-}
+    fn add_node(&mut self, node: Node, name: BString) {
+        let key = (name, node.kind());
 
-impl Node {
-    fn to_tree(&self) -> termtree::Tree<String> {
-        match self {
-            Node::Tree(tree) => tree.to_tree(),
-            Node::Summary(summary) => summary.to_tree(),
-            Node::Leaf(leaf) => leaf.to_tree(),
+        match (self.children.get_mut(&key), node) {
+            (Some(Node::Leaf(existing)), Node::Leaf(leaf)) => existing.merge(leaf),
+            (_, node) => {
+                self.children.insert(key, node);
+            }
         }
     }
 }
 
 impl Tree {
-    fn to_tree(&self) -> termtree::Tree<String> {
-        let mut tree = termtree::Tree::new(self.name.to_string_lossy().into_owned());
-
-        tree.extend(self.children.values().map(|child| child.to_tree()));
+    /// Merges each chain of directories that only contain a single directory into one node named
+    /// after the path to the innermost directory, e.g. `a/b/c`. The tree itself keeps its name.
+    fn compact(&mut self) {
+        self.children = std::mem::take(&mut self.children)
+            .into_iter()
+            .map(|(key, node)| match node {
+                Node::Tree(mut tree) => {
+                    tree.compact();
+
+                    while tree.children.len() == 1
+                        && matches!(tree.children.values().next(), Some(Node::Tree(_)))
+                    {
+                        let Some((_, Node::Tree(child))) = tree.children.pop_first() else {
+                            unreachable!("the only child is a directory");
+                        };
+
+                        tree.name.push(b'/');
+                        tree.name.extend_from_slice(&child.name);
+                        tree.children = child.children;
+                    }
 
-        tree
+                    ((tree.name.clone(), NodeKind::Directory), Node::Tree(tree))
+                }
+                node => (key, node),
+            })
+            .collect();
     }
-}
 
-impl Summary {
-    fn to_tree(&self) -> termtree::Tree<String> {
-        termtree::Tree::new(format!(
-            "{} {} +{} -{} ({})",
-            self.name.as_os_str().to_string_lossy(),
-            Fixed(244).paint(format!(
-                "[{}]",
-                self.stats.branch.as_os_str().to_string_lossy()
-            )),
-            Green.paint(format!("{}", self.stats.insertions)),
-            Red.paint(format!("{}", self.stats.deletions)),
-            Yellow.paint(format!("{}", self.stats.files_changed)),
-        ))
+    fn line_stat(&self) -> Option<LineStat> {
+        self.children
+            .values()
+            .filter_map(Node::line_stat)
+            .reduce(|mut total, stats| {
+                total.add(&stats);
+
+                total
+            })
     }
 }
 
-// http://www.calmar.ws/vim/256-xterm-24bit-rgb-color-chart.html
-impl Leaf {
-    fn to_tree(&self) -> termtree::Tree<String> {
-        let style = match self.status {
-            Status::WorktreeModified => Red.normal(),
-            Status::IndexModified => Red.bold(),
-            Status::WorktreeAdded => Green.normal(),
-            Status::IndexAdded => Green.bold(),
-            Status::Ignored => Blue.normal(),
-            _ => White.normal(),
-        };
-
-        let modifier_index = match self.status {
-            Status::IndexModified => "M",
-            Status::IndexAdded => "N",
-            Status::IndexRemoved => "D",
-            _ => "-",
-        };
-
-        let modifier_worktree = match self.status {
-            Status::WorktreeModified => "M",
-            Status::WorktreeAdded => "N",
-            // TODO:
-            // Mention "D" in help.
-            Status::WorktreeRemoved => "D",
-            _ => "-",
-        };
+impl Node {
+    fn kind(&self) -> NodeKind {
+        match self {
+            Node::Leaf(leaf) if leaf.collapsed_files.is_some() => NodeKind::CollapsedDirectory,
+            Node::Leaf(_) => NodeKind::File,
+            Node::Tree(_) | Node::Summary(_) | Node::Failure(_) => NodeKind::Directory,
+        }
+    }
 
-        let gray = Fixed(244).normal();
+    /// Whether this node shows anything that would be reported by `git status`. Ignored files
+    /// don't count as changes.
+    fn is_dirty(&self) -> bool {
+        match self {
+            Node::Tree(tree) => tree.children.values().any(Node::is_dirty),
+            Node::Summary(summary) => summary.stats.has_changes(),
+            Node::Leaf(leaf) => {
+                leaf.status.index.is_some()
+                    || !matches!(leaf.status.worktree, None | Some(Status::Ignored))
+            }
+            Node::Failure(_) => false,
+        }
+    }
 
-        termtree::Tree::new(format!(
-            "{}{} {}",
-            gray.paint(modifier_index),
-            gray.paint(modifier_worktree),
-            style.paint(format!("{}", self.name.as_os_str().to_string_lossy()))
-        ))
+    /// Adds up the changed files below this node and the lines added to and removed from them.
+    /// Returns `None` if no file below this node has line counts, i.e. without `--stat`.
+    fn line_stat(&self) -> Option<LineStat> {
+        match self {
+            Node::Tree(tree) => tree.line_stat(),
+            Node::Leaf(leaf) => leaf.stats.as_ref().map(|stats| LineStat {
+                // A file that is both staged and modified is still a single file.
+                files_changed: 1,
+                binary_files: stats.binary_files.min(1),
+                ..*stats
+            }),
+            Node::Summary(_) | Node::Failure(_) => None,
+        }
     }
-}
 
-fn walk_repository(repo: &Repository, name: &OsStr, args: &Args) -> Result<Option<Node>> {
-    if args.summary {
-        walk_summary(repo, name, args)
-    } else {
-        walk_entries(repo, name, args)
+    fn has_failures(&self) -> bool {
+        match self {
+            Node::Tree(tree) => tree.children.values().any(Node::has_failures),
+            Node::Failure(_) => true,
+            _ => false,
+        }
     }
 }
 
-fn walk_entries(repo: &Repository, name: &OsStr, args: &Args) -> Result<Option<Node>> {
-    let status = repo.status(gix::progress::Discard)?;
+/// The styles used to render the tree. The default theme is plain, it doesn't emit any escape
+/// sequences, so everything it shows also needs to be conveyed by text.
+#[derive(Debug, Default)]
+struct Theme {
+    modified: Style,
+    added: Style,
+    removed: Style,
+    renamed: Style,
+    copied: Style,
+    type_change: Style,
+    intent_to_add: Style,
+    staged_modified: Style,
+    staged_added: Style,
+    staged_removed: Style,
+    staged_renamed: Style,
+    staged_copied: Style,
+    conflict: Style,
+    ignored: Style,
+    /// The status columns in front of a file's name.
+    marker: Style,
+    /// The origin of a renamed or copied file.
+    source: Style,
+    branch: Style,
+    insertions: Style,
+    deletions: Style,
+    files_changed: Style,
+    conflicts: Style,
+    error: Style,
+}
 
-    let mut root = Tree {
-        name: name.into(),
-        children: BTreeMap::new(),
-    };
+type ThemeSlot = fn(&mut Theme) -> &mut Style;
+
+/// The names of the slots that can be configured via `tree.color.<slot>`, next to the style they
+/// set.
+const THEME_SLOTS: &[(&str, ThemeSlot)] = &[
+    ("modified", |theme| &mut theme.modified),
+    ("added", |theme| &mut theme.added),
+    ("removed", |theme| &mut theme.removed),
+    ("renamed", |theme| &mut theme.renamed),
+    ("copied", |theme| &mut theme.copied),
+    ("typeChange", |theme| &mut theme.type_change),
+    ("intentToAdd", |theme| &mut theme.intent_to_add),
+    ("stagedModified", |theme| &mut theme.staged_modified),
+    ("stagedAdded", |theme| &mut theme.staged_added),
+    ("stagedRemoved", |theme| &mut theme.staged_removed),
+    ("stagedRenamed", |theme| &mut theme.staged_renamed),
+    ("stagedCopied", |theme| &mut theme.staged_copied),
+    ("conflict", |theme| &mut theme.conflict),
+    ("ignored", |theme| &mut theme.ignored),
+    ("marker", |theme| &mut theme.marker),
+    ("source", |theme| &mut theme.source),
+    ("branch", |theme| &mut theme.branch),
+    ("insertions", |theme| &mut theme.insertions),
+    ("deletions", |theme| &mut theme.deletions),
+    ("filesChanged", |theme| &mut theme.files_changed),
+    ("conflicts", |theme| &mut theme.conflicts),
+    ("error", |theme| &mut theme.error),
+];
 
-    for item in status.into_iter(Vec::new())? {
-        let item = item?;
-        let status = item.clone().into();
+// http://www.calmar.ws/vim/256-xterm-24bit-rgb-color-chart.html
+impl Theme {
+    fn colored() -> Self {
+        Self {
+            modified: Red.normal(),
+            added: Green.normal(),
+            removed: Red.normal(),
+            renamed: Yellow.normal(),
+            copied: Yellow.normal(),
+            type_change: Cyan.normal(),
+            intent_to_add: Green.normal(),
+            staged_modified: Red.bold(),
+            staged_added: Green.bold(),
+            staged_removed: Red.bold(),
+            staged_renamed: Yellow.bold(),
+            staged_copied: Yellow.bold(),
+            conflict: Purple.bold(),
+            ignored: Blue.normal(),
+            marker: Fixed(244).normal(),
+            source: Fixed(244).normal(),
+            branch: Fixed(244).normal(),
+            insertions: Green.normal(),
+            deletions: Red.normal(),
+            files_changed: Yellow.normal(),
+            conflicts: Purple.normal(),
+            error: Red.normal(),
+        }
+    }
 
-        if args.all || !matches!(status, Status::Ignored) {
-            let path = Path::new(item.location().to_os_str()?);
-            let file_name = file_name(path);
+    /// Starts from the colored theme and replaces the styles set via `tree.color.<slot>`, e.g.
+    /// `tree.color.modified = bold yellow`. Values use git's color syntax.
+    fn from_config(config: &gix::config::File<'_>) -> Result<Self> {
+        let mut theme = Self::colored();
 
-            let parent_path = path.parent();
+        for (name, slot) in THEME_SLOTS {
+            let key = format!("tree.color.{name}");
 
-            if let Some(parent_path) = parent_path {
-                let leaf = Leaf {
-                    name: file_name.into(),
-                    status: item.clone().into(),
-                };
+            if let Some(value) = config.string(key.as_str()) {
+                let color = gix::config::Color::try_from(value.as_ref())
+                    .with_context(|| format!("invalid color {value:?} in {key}"))?;
 
-                root.add_leaf_at_path(leaf, &mut parent_path.components());
+                *slot(&mut theme) = color_to_style(&color);
             }
         }
-    }
 
-    Ok(Some(Node::Tree(root)))
-}
+        Ok(theme)
+    }
 
-fn file_name(path: &Path) -> &OsStr {
-    path.file_name().unwrap_or(path.as_os_str())
+    fn status(&self, status: &Status) -> Style {
+        match status {
+            Status::WorktreeModified => self.modified,
+            Status::WorktreeAdded => self.added,
+            Status::WorktreeRemoved => self.removed,
+            Status::WorktreeRenamed(_) => self.renamed,
+            Status::WorktreeCopied(_) => self.copied,
+            Status::TypeChange => self.type_change,
+            Status::IntentToAdd => self.intent_to_add,
+            Status::IndexModified => self.staged_modified,
+            Status::IndexAdded => self.staged_added,
+            Status::IndexRemoved => self.staged_removed,
+            Status::IndexRenamed(_) => self.staged_renamed,
+            Status::IndexCopied(_) => self.staged_copied,
+            Status::Conflict(_) => self.conflict,
+            Status::Ignored => self.ignored,
+        }
+    }
 }
 
-fn walk_summary(repo: &Repository, name: &OsStr, args: &Args) -> Result<Option<Node>> {
-    let stats: DiffStat = repo.try_into()?;
+fn color_to_style(color: &gix::config::Color) -> Style {
+    use gix::config::color::Attribute;
 
-    if args.only_show_changes && stats.insertions == 0 && stats.deletions == 0 {
-        return Ok(None);
-    }
+    let mut style = Style::new();
 
-    let summary = Summary {
-        name: name.into(),
-        stats,
-    };
+    style.foreground = color.foreground.and_then(color_name_to_colour);
+    style.background = color.background.and_then(color_name_to_colour);
+    style.is_bold = color.attributes.contains(Attribute::BOLD);
+    style.is_dimmed = color.attributes.contains(Attribute::DIM);
+    style.is_italic = color.attributes.contains(Attribute::ITALIC);
+    style.is_underline = color.attributes.contains(Attribute::UL);
+    style.is_blink = color.attributes.contains(Attribute::BLINK);
+    style.is_reverse = color.attributes.contains(Attribute::REVERSE);
+    style.is_strikethrough = color.attributes.contains(Attribute::STRIKE);
 
-    Ok(Some(Node::Summary(summary)))
+    style
 }
 
-fn walk_directory(path: &Path, iter: ReadDir, depth: usize, args: &Args) -> Result<Node> {
-    let mut tree = Tree {
-        name: file_name(path).into(),
-        children: BTreeMap::new(),
+fn color_name_to_colour(name: gix::config::color::Name) -> Option<Colour> {
+    use gix::config::color::Name;
+
+    let colour = match name {
+        Name::Normal | Name::Default => return None,
+        Name::Black => Colour::Black,
+        Name::Red => Colour::Red,
+        Name::Green => Colour::Green,
+        Name::Yellow => Colour::Yellow,
+        Name::Blue => Colour::Blue,
+        Name::Magenta => Colour::Purple,
+        Name::Cyan => Colour::Cyan,
+        Name::White => Colour::White,
+        Name::BrightBlack => Colour::Fixed(8),
+        Name::BrightRed => Colour::Fixed(9),
+        Name::BrightGreen => Colour::Fixed(10),
+        Name::BrightYellow => Colour::Fixed(11),
+        Name::BrightBlue => Colour::Fixed(12),
+        Name::BrightMagenta => Colour::Fixed(13),
+        Name::BrightCyan => Colour::Fixed(14),
+        Name::BrightWhite => Colour::Fixed(15),
+        Name::Ansi(code) => Colour::Fixed(code),
+        Name::Rgb(r, g, b) => Colour::RGB(r, g, b),
     };
 
-    let directories = iter.filter_map(|e| e.ok()).collect::<Vec<_>>();
+    Some(colour)
+}
 
-    let new_entries = directories
-        .iter()
-        .filter_map(|entry| {
-            walk_path(&entry.path(), depth - 1, args)
-                .ok()
-                .and_then(|child| child.map(|child| (child, entry.file_name())))
-        })
-        .collect::<Vec<(Node, OsString)>>();
+/// Bounds the size of the rendered tree, see `--max-depth` and `--max-entries`.
+#[derive(Clone, Copy, Debug, Default)]
+struct Limits {
+    /// The number of levels shown below the current directory, `None` for no limit.
+    depth: Option<usize>,
+    /// The number of entries shown per directory, `None` for no limit.
+    entries: Option<NonZeroUsize>,
+}
 
-    for (node, file_name) in new_entries {
-        tree.add_node(node, file_name)
+impl Limits {
+    fn descend(self) -> Self {
+        Self {
+            depth: self.depth.map(|depth| depth.saturating_sub(1)),
+            ..self
+        }
     }
-
-    Ok(Node::Tree(tree))
 }
 
-fn walk_path(path: &Path, depth: usize, args: &Args) -> Result<Option<Node>> {
-    if path.is_dir() {
-        match gix::open(path) {
-            Ok(repo) => {
-                let node = walk_repository(&repo, file_name(path), args)?;
+impl Node {
+    fn to_tree(&self, theme: &Theme, limits: Limits) -> termtree::Tree<String> {
+        match self {
+            Node::Tree(tree) => tree.to_tree(theme, limits),
+            Node::Summary(summary) => summary.to_tree(theme),
+            Node::Leaf(leaf) => leaf.to_tree(theme),
+            Node::Failure(failure) => failure.to_tree(theme),
+        }
+    }
+}
 
-                Ok(node)
-            }
+impl Tree {
+    fn to_tree(&self, theme: &Theme, limits: Limits) -> termtree::Tree<String> {
+        let mut label = self.name.to_str_lossy().into_owned();
+
+        // The contents of directories below `--max-depth` are hidden.
+        let truncated = limits.depth == Some(0) && !self.children.is_empty();
+
+        // Every directory shows the number of changed files below it, `--stat` adds the number of
+        // lines added and removed.
+        if let Some(stats) = self.line_stat() {
+            label.push_str(&format!(
+                " ({} {}, +{} -{})",
+                theme
+                    .files_changed
+                    .paint(format!("{}", stats.files_changed)),
+                if stats.files_changed == 1 {
+                    "file"
+                } else {
+                    "files"
+                },
+                theme.insertions.paint(format!("{}", stats.insertions)),
+                theme.deletions.paint(format!("{}", stats.deletions)),
+            ));
+        } else if let files @ 1.. = self.count_leaves() {
+            label.push_str(&format!(
+                " ({} {})",
+                theme.files_changed.paint(format!("{files}")),
+                if files == 1 { "file" } else { "files" },
+            ));
+        }
+
+        let mut tree = termtree::Tree::new(label);
+
+        if truncated {
+            return tree;
+        }
+
+        let shown = limits
+            .entries
+            .map_or(self.children.len(), NonZeroUsize::get);
+
+        tree.extend(
+            self.children
+                .values()
+                .take(shown)
+                .map(|child| child.to_tree(theme, limits.descend())),
+        );
+
+        if let Some(hidden) = self
+            .children
+            .len()
+            .checked_sub(shown)
+            .filter(|&hidden| hidden > 0)
+        {
+            tree.push(termtree::Tree::new(
+                theme
+                    .marker
+                    .paint(format!("… and {hidden} more"))
+                    .to_string(),
+            ));
+        }
+
+        tree
+    }
+
+    /// Counts the files below this directory, no matter how deeply nested they are.
+    fn count_leaves(&self) -> usize {
+        self.children
+            .values()
+            .map(|child| match child {
+                Node::Tree(tree) => tree.count_leaves(),
+                Node::Leaf(_) => 1,
+                Node::Summary(_) | Node::Failure(_) => 0,
+            })
+            .sum()
+    }
+}
+
+impl LineStat {
+    fn to_label(&self, theme: &Theme) -> String {
+        format!(
+            "+{} -{} ({})",
+            theme.insertions.paint(format!("{}", self.insertions)),
+            theme.deletions.paint(format!("{}", self.deletions)),
+            theme.files_changed.paint(format!("{}", self.files_changed)),
+        )
+    }
+}
+
+impl Summary {
+    fn to_tree(&self, theme: &Theme) -> termtree::Tree<String> {
+        let mut branch = self.stats.branch.to_str_lossy().into_owned();
+
+        if let Some(upstream) = &self.stats.upstream {
+            if upstream.ahead > 0 {
+                branch.push_str(&format!(" ↑{}", upstream.ahead));
+            }
+            if upstream.behind > 0 {
+                branch.push_str(&format!(" ↓{}", upstream.behind));
+            }
+        }
+
+        let mut label = format!(
+            "{} {} staged {} unstaged {}",
+            self.name.to_str_lossy(),
+            theme.branch.paint(format!("[{branch}]")),
+            self.stats.staged.to_label(theme),
+            self.stats.unstaged.to_label(theme),
+        );
+
+        if self.stats.conflicts > 0 {
+            label.push_str(&format!(
+                " conflicts {}",
+                theme.conflicts.paint(format!("{}", self.stats.conflicts))
+            ));
+        }
+
+        termtree::Tree::new(label)
+    }
+}
+
+impl Failure {
+    fn to_tree(&self, theme: &Theme) -> termtree::Tree<String> {
+        termtree::Tree::new(format!(
+            "{} {}",
+            self.name.to_str_lossy(),
+            theme.error.paint(format!("error: {}", self.message))
+        ))
+    }
+}
+
+impl Leaf {
+    fn to_tree(&self, theme: &Theme) -> termtree::Tree<String> {
+        // Changes to the index take precedence over changes to the working tree when it comes to
+        // choosing a color, e.g. a new file that has been modified after being staged is shown
+        // as new.
+        let primary = self.status.index.as_ref().or(self.status.worktree.as_ref());
+
+        let style = primary.map_or_else(Style::new, |status| theme.status(status));
+
+        let modifier_index = match self.status.index {
+            Some(Status::IndexModified) => "M",
+            Some(Status::IndexAdded) => "N",
+            Some(Status::IndexRemoved) => "D",
+            Some(Status::IndexRenamed(_)) => "R",
+            Some(Status::IndexCopied(_)) => "C",
+            _ => "-",
+        };
+
+        let modifier_worktree = match self.status.worktree {
+            Some(Status::WorktreeModified) => "M",
+            Some(Status::WorktreeAdded) => "N",
+            Some(Status::WorktreeRemoved) => "D",
+            Some(Status::WorktreeRenamed(_)) => "R",
+            Some(Status::WorktreeCopied(_)) => "C",
+            Some(Status::TypeChange) => "T",
+            Some(Status::IntentToAdd) => "A",
+            Some(Status::Ignored) => "!",
+            _ => "-",
+        };
+
+        // Unmerged paths use both columns to describe the conflict, the way `git status --short`
+        // does.
+        let (modifier_index, modifier_worktree, modifier_style) = match self.status.worktree {
+            Some(Status::Conflict(conflict)) => {
+                let (ours, theirs) = conflict_modifiers(conflict);
+
+                (ours, theirs, theme.conflict)
+            }
+            _ => (modifier_index, modifier_worktree, theme.marker),
+        };
+
+        // If a file has been renamed in the index and renamed again in the working tree, its
+        // original location is the one from the index.
+        let rewrite = match primary.and_then(Status::rewrite) {
+            Some(rewrite) => theme
+                .source
+                .paint(format!(
+                    " ← {} ({:.0}%)",
+                    rewrite.source,
+                    rewrite.similarity * 100.0
+                ))
+                .to_string(),
+            None => String::new(),
+        };
+
+        let name = match self.collapsed_files {
+            Some(files) => format!(
+                "{}/ ({} {})",
+                style.paint(format!("{}", self.name.to_str_lossy())),
+                theme.files_changed.paint(format!("{files}")),
+                if files == 1 { "file" } else { "files" },
+            ),
+            None => style
+                .paint(format!("{}", self.name.to_str_lossy()))
+                .to_string(),
+        };
+
+        let stats = match &self.stats {
+            Some(stats) if stats.binary_files > 0 => " bin".to_string(),
+            Some(stats) => format!(
+                " +{} -{}",
+                theme.insertions.paint(format!("{}", stats.insertions)),
+                theme.deletions.paint(format!("{}", stats.deletions)),
+            ),
+            None => String::new(),
+        };
+
+        termtree::Tree::new(format!(
+            "{}{} {}{}{}",
+            modifier_style.paint(modifier_index),
+            modifier_style.paint(modifier_worktree),
+            name,
+            rewrite,
+            stats
+        ))
+    }
+}
+
+/// The version of the JSON document emitted by `--format json`. This is to be incremented whenever
+/// the schema changes in a way that is not backwards compatible.
+const JSON_SCHEMA_VERSION: u64 = 1;
+
+fn child_path(parent: &str, name: &BStr) -> String {
+    let name = name.to_str_lossy();
+
+    if parent.is_empty() {
+        name.into_owned()
+    } else {
+        format!("{parent}/{name}")
+    }
+}
+
+impl Node {
+    fn to_json(&self, path: &str) -> serde_json::Value {
+        match self {
+            Node::Tree(tree) => tree.to_json(path),
+            Node::Summary(summary) => summary.to_json(path),
+            Node::Leaf(leaf) => leaf.to_json(path),
+            Node::Failure(failure) => failure.to_json(path),
+        }
+    }
+}
+
+impl Node {
+    /// Collects one record per repository summary or changed file, skipping the directories in
+    /// between. Records are the same objects that make up the JSON document.
+    fn to_json_records(&self, path: &str, records: &mut Vec<serde_json::Value>) {
+        match self {
+            Node::Tree(tree) => {
+                for ((name, _), child) in &tree.children {
+                    child.to_json_records(&child_path(path, name.as_ref()), records);
+                }
+            }
+            _ => records.push(self.to_json(path)),
+        }
+    }
+}
+
+impl Tree {
+    fn to_json(&self, path: &str) -> serde_json::Value {
+        let children = self
+            .children
+            .iter()
+            .map(|((name, _), child)| child.to_json(&child_path(path, name.as_ref())))
+            .collect::<Vec<_>>();
+
+        json!({
+            "type": "tree",
+            "name": self.name.to_str_lossy(),
+            "path": path,
+            "children": children,
+        })
+    }
+}
+
+impl Summary {
+    fn to_json(&self, path: &str) -> serde_json::Value {
+        let total = self.stats.total();
+
+        json!({
+            "type": "summary",
+            "name": self.name.to_str_lossy(),
+            "path": path,
+            "branch": self.stats.branch.to_str_lossy(),
+            "upstream": self.stats.upstream.as_ref().map(|upstream| json!({
+                "name": upstream.name.to_str_lossy(),
+                "ahead": upstream.ahead,
+                "behind": upstream.behind,
+            })),
+            "files_changed": total.files_changed,
+            "insertions": total.insertions,
+            "deletions": total.deletions,
+            "staged": self.stats.staged.to_json(),
+            "unstaged": self.stats.unstaged.to_json(),
+            "conflicts": self.stats.conflicts,
+        })
+    }
+}
+
+impl LineStat {
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "files_changed": self.files_changed,
+            "insertions": self.insertions,
+            "deletions": self.deletions,
+        })
+    }
+}
+
+impl Leaf {
+    fn to_json(&self, path: &str) -> serde_json::Value {
+        json!({
+            "type": "leaf",
+            "name": self.name.to_str_lossy(),
+            "path": path,
+            "index_status": self.status.index.as_ref().map(Status::to_json_name),
+            "worktree_status": self.status.worktree.as_ref().map(Status::to_json_name),
+            "index_rewrite": self.status.index.as_ref().and_then(Status::rewrite).map(Rewrite::to_json),
+            "worktree_rewrite": self.status.worktree.as_ref().and_then(Status::rewrite).map(Rewrite::to_json),
+            "conflict": match self.status.worktree {
+                Some(Status::Conflict(conflict)) => Some(conflict_to_json_name(conflict)),
+                _ => None,
+            },
+            "insertions": self.stats.as_ref().map(|stats| stats.insertions),
+            "deletions": self.stats.as_ref().map(|stats| stats.deletions),
+            "binary": self.stats.as_ref().map(|stats| stats.binary_files > 0),
+            "collapsed_files": self.collapsed_files,
+        })
+    }
+}
+
+impl Failure {
+    fn to_json(&self, path: &str) -> serde_json::Value {
+        json!({
+            "type": "error",
+            "name": self.name.to_str_lossy(),
+            "path": path,
+            "message": self.message,
+        })
+    }
+}
+
+impl Rewrite {
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "source": self.source.to_str_lossy(),
+            "similarity": (self.similarity * 100.0).round() as u8,
+        })
+    }
+}
+
+/// The git configuration that applies to the paths given on the command line: the repository's
+/// configuration if there is a single path that is a repository (or inside one, unless `--depth`
+/// is given), the global configuration otherwise. Both include overrides from the environment.
+fn config(paths: &[PathBuf], depth: usize) -> Result<gix::config::File<'static>> {
+    if let [path] = paths {
+        let repo = if depth == 0 {
+            gix::discover(path).ok()
+        } else {
+            gix::open(path).ok()
+        };
+
+        if let Some(repo) = repo {
+            return Ok(repo.config_snapshot().plumbing().clone());
+        }
+    }
+
+    let mut config = gix::config::File::from_globals()?;
+
+    config.append(gix::config::File::from_environment_overrides()?);
+
+    Ok(config)
+}
+
+/// Walks `repo`, computing its status on the threads that are not busy with other repositories.
+fn walk_repository(
+    repo: &Repository,
+    name: &OsStr,
+    args: &Args,
+    jobs: &Jobs,
+) -> Result<Option<Node>> {
+    jobs.borrow(|threads| {
+        if args.summary {
+            walk_summary(repo, name, args, threads)
+        } else {
+            walk_entries(repo, name, args, threads)
+        }
+    })
+}
+
+/// Prepares the status of `repo`, checking tracked files for modifications on no more than
+/// `threads` threads. With `--against`, the index is compared to the given revision instead of
+/// `HEAD`.
+fn status<'repo>(
+    repo: &'repo Repository,
+    args: &Args,
+    threads: NonZeroUsize,
+) -> Result<gix::status::Platform<'repo, gix::progress::Discard>> {
+    let mut status = repo
+        .status(gix::progress::Discard)?
+        .index_worktree_options_mut(|options| {
+            options.thread_limit = Some(threads.get());
+        });
+
+    if let Some(rev) = &args.against {
+        let tree = repo
+            .rev_parse_single(rev.as_str())
+            .map_err(anyhow::Error::from)
+            .and_then(|id| Ok(id.object()?.peel_to_tree()?.id))
+            .with_context(|| format!("could not resolve {rev:?} to a tree"))?;
+
+        status = status.head_tree(tree);
+    }
+
+    Ok(status)
+}
+
+fn walk_entries(
+    repo: &Repository,
+    name: &OsStr,
+    args: &Args,
+    threads: NonZeroUsize,
+) -> Result<Option<Node>> {
+    use gix::status::index_worktree::Item;
+
+    let mut status = status(repo, args, threads)?;
+
+    if let Some(rewrites) = args.rewrites() {
+        status = status
+            .tree_index_track_renames(gix::status::tree_index::TrackRenames::Given(rewrites))
+            .index_worktree_rewrites(rewrites);
+
+        // Untracked files can only be matched against deleted files if they are not collapsed
+        // into their parent directory. This is only the default, an untracked mode chosen by
+        // the user still applies.
+        if repo
+            .config_snapshot()
+            .string("status.showUntrackedFiles")
+            .is_none()
+        {
+            status = status.untracked_files(gix::status::UntrackedFiles::Files);
+        }
+    } else if args.no_renames {
+        status = status.tree_index_track_renames(gix::status::tree_index::TrackRenames::Disabled);
+    }
+
+    // Without `--untracked-files`, `status.showUntrackedFiles` applies.
+    if let Some(untracked_files) = args.untracked_files {
+        status = status.untracked_files(untracked_files.into());
+    }
+
+    if args.shows_ignored() {
+        // Ignored entries inside an untracked directory are shown on their own, the way `git
+        // status --ignored` does, instead of being collapsed into the untracked directory.
+        status = status.dirwalk_options(|options| {
+            options
+                .emit_ignored(Some(gix::dir::walk::EmissionMode::CollapseDirectory))
+                .emit_collapsed(Some(
+                    gix::dir::walk::CollapsedEntriesEmissionMode::OnStatusMismatch,
+                ))
+        });
+    }
+
+    let mut root = Tree {
+        name: to_bstring(name),
+        children: BTreeMap::new(),
+    };
+
+    for item in status.into_iter(args.patterns())? {
+        let item = item?;
+        let status = Status::from_item(repo, item.clone())?;
+
+        if (args.shows_ignored() || !matches!(status, Status::Ignored)) && args.shows(&status) {
+            let stats = match status {
+                Status::Ignored => None,
+                _ if args.stat => item_stats(repo, &item)?,
+                _ => None,
+            };
+
+            let collapsed_files = match &item {
+                gix::status::Item::IndexWorktree(Item::DirectoryContents { entry, .. })
+                    if entry.disk_kind == Some(gix::dir::entry::Kind::Directory) =>
+                {
+                    match entry.status {
+                        gix::dir::entry::Status::Untracked => {
+                            Some(count_untracked_files(repo, entry.rela_path.as_bstr())?)
+                        }
+                        _ => {
+                            let path = repo
+                                .workdir()
+                                .context("untracked files require a working tree")?
+                                .join(gix::path::from_bstr(entry.rela_path.as_bstr()));
+
+                            Some(count_files(&path)?)
+                        }
+                    }
+                }
+                _ => None,
+            };
+
+            root.add_leaf_at_location(item.location(), status.into(), stats, collapsed_files);
+        }
+    }
+
+    Ok(Some(Node::Tree(root)))
+}
+
+/// Resolves the revisions given to `git-tree diff` to the commits whose trees are compared. A single
+/// revision is either a range, `a..b` or `a...b`, or compared to `HEAD`. `a...b` compares `b` to the
+/// merge base of `a` and `b`, the way `git diff a...b` does.
+fn resolve_revisions(
+    repo: &Repository,
+    from: &str,
+    to: Option<&str>,
+) -> Result<(ObjectId, ObjectId)> {
+    use gix::revision::plumbing::Spec;
+
+    let resolve = |rev: &str| -> Result<ObjectId> {
+        Ok(repo
+            .rev_parse_single(rev)
+            .with_context(|| format!("could not resolve {rev:?}"))?
+            .detach())
+    };
+
+    if let Some(to) = to {
+        return Ok((resolve(from)?, resolve(to)?));
+    }
+
+    let spec = repo
+        .rev_parse(from)
+        .with_context(|| format!("could not resolve {from:?}"))?
+        .detach();
+
+    match spec {
+        Spec::Include(id) => Ok((id, repo.head_id()?.detach())),
+        Spec::Range { from, to } => Ok((from, to)),
+        Spec::Merge { theirs, ours } => Ok((repo.merge_base(theirs, ours)?.detach(), ours)),
+        _ => Err(anyhow!(
+            "unsupported revision {from:?}, expected a revision or a range"
+        )),
+    }
+}
+
+/// Builds the tree of files that differ between two revisions.
+fn walk_diff(repo: &Repository, diff: &DiffArgs, args: &Args) -> Result<Node> {
+    let (from, to) = resolve_revisions(repo, &diff.from, diff.to.as_deref())?;
+
+    let old_tree = repo.find_object(from)?.peel_to_tree()?;
+    let new_tree = repo.find_object(to)?.peel_to_tree()?;
+
+    let name = match &diff.to {
+        Some(to) => format!("{}..{}", diff.from, to),
+        None => diff.from.clone(),
+    };
+
+    walk_tree_changes(repo, name.into(), Some(&old_tree), &new_tree, args)
+}
+
+/// Builds the tree of files a single commit changed compared to one of its parents. The root is
+/// labelled with the commit's abbreviated id and summary.
+fn walk_show(repo: &Repository, show: &ShowArgs, args: &Args) -> Result<Node> {
+    let commit = repo
+        .rev_parse_single(show.commit.as_str())
+        .with_context(|| format!("could not resolve {:?}", show.commit))?
+        .object()?
+        .peel_to_commit()?;
+
+    let parents = commit.parent_ids().collect::<Vec<_>>();
+
+    // A root commit is compared to the empty tree.
+    let old_tree = match parents.get(show.parent.get() - 1) {
+        Some(parent) => Some(parent.object()?.peel_to_tree()?),
+        None if parents.is_empty() && show.parent == NonZeroUsize::MIN => None,
+        None => {
+            return Err(anyhow!(
+                "{:?} has {} parent(s), there is no parent {}",
+                show.commit,
+                parents.len(),
+                show.parent
+            ))
+        }
+    };
+    let new_tree = commit.tree()?;
+
+    let name = format!(
+        "{} {}",
+        commit.id().shorten_or_id(),
+        commit.message()?.summary()
+    );
+
+    walk_tree_changes(repo, name.into(), old_tree.as_ref(), &new_tree, args)
+}
+
+/// Builds a tree of the files that differ between `old_tree` and `new_tree`, along with the number
+/// of lines added and removed. A missing `old_tree` stands for the empty tree.
+fn walk_tree_changes(
+    repo: &Repository,
+    name: BString,
+    old_tree: Option<&gix::Tree<'_>>,
+    new_tree: &gix::Tree<'_>,
+    args: &Args,
+) -> Result<Node> {
+    use gix::object::tree::diff::ChangeDetached;
+
+    let options = if args.rewrites().is_some() || args.no_renames {
+        Some(gix::diff::Options::default().with_rewrites(args.rewrites()))
+    } else {
+        None
+    };
+
+    let mut root = Tree {
+        name,
+        children: BTreeMap::new(),
+    };
+
+    for change in repo.diff_tree_to_tree(old_tree, new_tree, options)? {
+        if change.entry_mode().is_tree() {
+            continue;
+        }
+
+        let (status, previous_id, id) = match &change {
+            ChangeDetached::Addition { id, .. } => (Status::IndexAdded, None, Some(*id)),
+            ChangeDetached::Deletion { id, .. } => (Status::IndexRemoved, Some(*id), None),
+            ChangeDetached::Modification {
+                previous_id, id, ..
+            } => (Status::IndexModified, Some(*previous_id), Some(*id)),
+            ChangeDetached::Rewrite {
+                source_location,
+                source_id,
+                diff,
+                id,
+                copy,
+                ..
+            } => {
+                let rewrite = Rewrite {
+                    source: source_location.clone(),
+                    // `diff` is `None` if source and destination are identical.
+                    similarity: diff.map_or(1.0, |diff| diff.similarity),
+                };
+
+                let status = if *copy {
+                    Status::IndexCopied(rewrite)
+                } else {
+                    Status::IndexRenamed(rewrite)
+                };
+
+                (status, Some(*source_id), Some(*id))
+            }
+        };
+
+        if !args.shows(&status) {
+            continue;
+        }
+
+        // Submodules have no lines that could be counted.
+        let stats = if change.entry_mode().is_commit() {
+            None
+        } else {
+            let mut stats = LineStat::default();
+
+            calculate_stats(
+                repo,
+                None,
+                previous_id,
+                None,
+                id,
+                change.location(),
+                &mut stats,
+            )?;
+
+            Some(stats)
+        };
+
+        root.add_leaf_at_location(change.location(), status.into(), stats, None);
+    }
+
+    Ok(Node::Tree(root))
+}
+
+/// Counts the files below `path`, descending into subdirectories.
+fn count_files(path: &Path) -> Result<usize> {
+    let mut count = 0;
+
+    for entry in path.read_dir()? {
+        let entry = entry?;
+
+        if entry.file_type()?.is_dir() {
+            count += count_files(&entry.path())?;
+        } else {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Counts the untracked files below the untracked directory at `rela_path`, leaving out ignored
+/// files, which are shown separately.
+fn count_untracked_files(repo: &Repository, rela_path: &BStr) -> Result<usize> {
+    let options = repo
+        .dirwalk_options()?
+        .emit_untracked(gix::dir::walk::EmissionMode::Matching);
+    let pattern = BString::from(format!(":(top,literal){rela_path}"));
+
+    let mut count = 0;
+
+    for item in repo.dirwalk_iter(
+        repo.index_or_empty()?,
+        Some(pattern),
+        Default::default(),
+        options,
+    )? {
+        if item?.entry.disk_kind != Some(gix::dir::entry::Kind::Directory) {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Converts a name read from the file system, keeping its bytes where the platform allows it and
+/// replacing what can't be represented otherwise.
+fn to_bstring(name: &OsStr) -> BString {
+    match gix::path::os_str_into_bstr(name) {
+        Ok(name) => name.to_owned(),
+        Err(_) => name.to_string_lossy().as_ref().into(),
+    }
+}
+
+fn walk_summary(
+    repo: &Repository,
+    name: &OsStr,
+    args: &Args,
+    threads: NonZeroUsize,
+) -> Result<Option<Node>> {
+    let stats = DiffStat::new(repo, args, threads)?;
+
+    if args.only_show_changes && !stats.has_changes() {
+        return Ok(None);
+    }
+
+    let summary = Summary {
+        name: to_bstring(name),
+        stats,
+    };
+
+    Ok(Some(Node::Summary(summary)))
+}
+
+/// Limits the number of threads used to walk directories and compute the status of repositories.
+/// Nested directories share the same budget, so no more than `--jobs` threads are busy at any time.
+struct Jobs {
+    /// The number of threads that can be started in addition to the ones already running.
+    available: AtomicUsize,
+}
+
+impl Jobs {
+    fn new(jobs: NonZeroUsize) -> Self {
+        Self {
+            // The main thread counts as one of the jobs.
+            available: AtomicUsize::new(jobs.get() - 1),
+        }
+    }
+
+    fn acquire(&self, wanted: usize) -> usize {
+        let previous = self
+            .available
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |available| {
+                Some(available - available.min(wanted))
+            })
+            .unwrap_or_default();
+
+        previous.min(wanted)
+    }
+
+    fn release(&self) {
+        self.available.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Runs `f` with the calling thread and every thread that is available at the moment, so that a
+    /// single repository can use all of them. The threads are given back once `f` returns.
+    fn borrow<R>(&self, f: impl FnOnce(NonZeroUsize) -> R) -> R {
+        let borrowed = self.acquire(usize::MAX);
+
+        let result = f(NonZeroUsize::MIN.saturating_add(borrowed));
+
+        self.available.fetch_add(borrowed, Ordering::AcqRel);
+
+        result
+    }
+
+    /// Applies `f` to every item, using as many threads as are available. The calling thread takes
+    /// part in the work, so this makes progress even if no additional threads are available. The
+    /// results are returned in the same order as `items`.
+    fn map<T, R, F>(&self, items: &[T], f: F) -> Vec<R>
+    where
+        T: Sync,
+        R: Send,
+        F: Fn(&T) -> R + Sync,
+    {
+        let next = AtomicUsize::new(0);
+
+        let work = || {
+            let mut results = Vec::new();
+
+            loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+
+                match items.get(index) {
+                    Some(item) => results.push((index, f(item))),
+                    None => return results,
+                }
+            }
+        };
+
+        let mut results = thread::scope(|scope| {
+            let threads = (0..self.acquire(items.len().saturating_sub(1)))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let results = work();
+
+                        self.release();
+
+                        results
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut results = work();
+
+            for thread in threads {
+                match thread.join() {
+                    Ok(more_results) => results.extend(more_results),
+                    Err(payload) => std::panic::resume_unwind(payload),
+                }
+            }
+
+            results
+        });
+
+        results.sort_by_key(|(index, _)| *index);
+
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+fn walk_directory(
+    name: &OsStr,
+    iter: ReadDir,
+    depth: usize,
+    args: &Args,
+    jobs: &Jobs,
+) -> Result<Node> {
+    let mut tree = Tree {
+        name: to_bstring(name),
+        children: BTreeMap::new(),
+    };
+
+    let directories = iter.filter_map(|e| e.ok()).collect::<Vec<_>>();
+
+    let results = jobs.map(&directories, |entry| {
+        walk_path(&entry.path(), &entry.file_name(), depth - 1, args, jobs)
+    });
+
+    for (entry, result) in directories.iter().zip(results) {
+        let file_name = entry.file_name();
+
+        match result {
+            Ok(Some(node)) => tree.add_node(node, to_bstring(&file_name)),
+            Ok(None) => {}
+            Err(err) => tree.add_node(
+                failure(&entry.path(), &file_name, err, args)?,
+                to_bstring(&file_name),
+            ),
+        }
+    }
+
+    Ok(Node::Tree(tree))
+}
+
+/// Turns an error that occurred while walking `path` into a node, so that the affected repository
+/// shows up in the output instead of silently disappearing. With `--strict`, the error is returned
+/// instead.
+fn failure(path: &Path, name: &OsStr, err: anyhow::Error, args: &Args) -> Result<Node> {
+    if args.strict {
+        return Err(anyhow!("failed to read {:?}: {:#}", path, err));
+    }
+
+    Ok(Node::Failure(Failure {
+        name: to_bstring(name),
+        message: format!("{:#}", err),
+    }))
+}
+
+fn walk_path(
+    path: &Path,
+    name: &OsStr,
+    depth: usize,
+    args: &Args,
+    jobs: &Jobs,
+) -> Result<Option<Node>> {
+    if path.is_dir() {
+        match gix::open(path) {
+            Ok(repo) => {
+                let node = walk_repository(&repo, name, args, jobs)?;
+
+                Ok(node)
+            }
+
+            Err(err) if !matches!(err, gix::open::Error::NotARepository { .. }) => Err(err.into()),
 
             _ => {
                 if depth > 0 {
-                    let node = walk_directory(path, path.read_dir()?, depth, args)?;
+                    let node = walk_directory(name, path.read_dir()?, depth, args, jobs)?;
 
                     Ok(Some(node))
                 } else {
@@ -481,7 +1983,7 @@ fn walk_path(path: &Path, depth: usize, args: &Args) -> Result<Option<Node>> {
     }
 }
 
-fn fallback(path: &Path, args: &Args) -> Result<Option<Node>> {
+fn fallback(path: &Path, name: &Path, args: &Args, jobs: &Jobs) -> Result<Option<Node>> {
     let repo = gix::discover(path).with_context(|| {
         format!(
             "no git repository found at {:?}, you might want to try running git-tree with \
@@ -490,7 +1992,223 @@ fn fallback(path: &Path, args: &Args) -> Result<Option<Node>> {
         )
     })?;
 
-    walk_repository(&repo, file_name(path), args)
+    walk_repository(
+        &repo,
+        repository_root(&repo, path, name).as_os_str(),
+        args,
+        jobs,
+    )
+}
+
+/// Spells the root of `repo`'s working tree the way `name` spells `path`, a directory inside of
+/// it, e.g. `.` for `src/deep`. The tree below a repository always starts at its root, so that's
+/// what it is labelled with.
+fn repository_root(repo: &Repository, path: &Path, name: &Path) -> PathBuf {
+    let prefix = repo
+        .workdir()
+        .and_then(|workdir| Some((workdir.canonicalize().ok()?, path.canonicalize().ok()?)))
+        .and_then(|(workdir, path)| Some(path.strip_prefix(workdir).ok()?.to_owned()));
+
+    let Some(prefix) = prefix else {
+        return name.to_owned();
+    };
+
+    let mut root = name.to_owned();
+
+    for _ in prefix.components() {
+        match root.components().next_back() {
+            Some(Component::Normal(_)) => {
+                root.pop();
+            }
+            Some(Component::CurDir) => {
+                root.pop();
+                root.push("..");
+            }
+            _ => root.push(".."),
+        }
+    }
+
+    if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        root
+    }
+}
+
+/// Walks one of the paths given on the command line. The resulting tree's root is labelled with
+/// `name`, the path as given relative to the common root of all paths.
+fn walk_root(path: &Path, name: &Path, args: &Args, jobs: &Jobs) -> Result<Option<Node>> {
+    match walk_path(path, name.as_os_str(), args.depth, args, jobs)? {
+        node @ Some(_) => Ok(node),
+        None => fallback(path, name, args, jobs),
+    }
+}
+
+/// Finds the deepest directory that contains all of `paths` and spells each path relative to it.
+/// Relative paths are made absolute if any of the paths is absolute. The common root is `.` if the
+/// paths have nothing in common.
+fn common_root(given: &[&PathBuf]) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let paths = if given.iter().any(|path| path.is_absolute()) {
+        given
+            .iter()
+            .map(std::path::absolute)
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        given
+            .iter()
+            .map(|path| {
+                path.components()
+                    .filter(|component| !matches!(component, Component::CurDir))
+                    .collect::<PathBuf>()
+            })
+            .collect()
+    };
+
+    let mut common_root = paths.first().cloned().unwrap_or_default();
+
+    for path in &paths {
+        while !path.starts_with(&common_root) {
+            common_root.pop();
+        }
+    }
+
+    // Every path needs a name of its own below the root.
+    if paths.contains(&common_root) {
+        common_root.pop();
+    }
+
+    let names = paths
+        .iter()
+        .zip(given)
+        .map(|(path, given)| match path.strip_prefix(&common_root) {
+            Ok(name) if !name.as_os_str().is_empty() => name.to_owned(),
+            _ => given.to_path_buf(),
+        })
+        .collect();
+
+    if common_root.as_os_str().is_empty() {
+        common_root = PathBuf::from(".");
+    }
+
+    Ok((common_root, names))
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    /// A colored tree
+    Tree,
+    /// A JSON document describing the tree, its schema is identified by the
+    /// top-level `version` key and described in the README
+    Json,
+    /// Newline-delimited JSON, one record per repository summary or changed
+    /// file, using the same schema as the records in `json`
+    Ndjson,
+}
+
+/// Mirrors git's `status.showUntrackedFiles`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ShowUntrackedFiles {
+    /// Don't show untracked files
+    No,
+    /// Show untracked directories as a single entry along with the number of
+    /// files they contain
+    Normal,
+    /// Show every untracked file
+    All,
+}
+
+impl From<ShowUntrackedFiles> for gix::status::UntrackedFiles {
+    fn from(show: ShowUntrackedFiles) -> Self {
+        match show {
+            ShowUntrackedFiles::No => gix::status::UntrackedFiles::None,
+            ShowUntrackedFiles::Normal => gix::status::UntrackedFiles::Collapsed,
+            ShowUntrackedFiles::All => gix::status::UntrackedFiles::Files,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum StatusFilter {
+    /// Changes between `HEAD` and the index
+    Staged,
+    /// Changes to tracked files that have not been staged
+    Unstaged,
+    /// Files that are not tracked
+    Untracked,
+    /// Unmerged paths
+    Conflict,
+    /// Ignored files, implies --all
+    Ignored,
+}
+
+/// The letters given to `--diff-filter`, split into the kinds of changes to show and the ones to
+/// leave out.
+#[derive(Clone, Debug)]
+struct DiffFilter {
+    include: Vec<u8>,
+    exclude: Vec<u8>,
+}
+
+impl DiffFilter {
+    const LETTERS: &'static [u8] = b"ACDMRTU";
+
+    fn parse(value: &str) -> Result<Self, String> {
+        let mut filter = DiffFilter {
+            include: Vec::new(),
+            exclude: Vec::new(),
+        };
+
+        for letter in value.bytes() {
+            if Self::LETTERS.contains(&letter) {
+                filter.include.push(letter);
+            } else if Self::LETTERS.contains(&letter.to_ascii_uppercase()) {
+                filter.exclude.push(letter.to_ascii_uppercase());
+            } else {
+                return Err(format!(
+                    "unknown status {:?}, expected one of {}",
+                    char::from(letter),
+                    Self::LETTERS.as_bstr()
+                ));
+            }
+        }
+
+        Ok(filter)
+    }
+
+    /// Uppercase letters select the kinds of changes to show, lowercase letters the ones to leave
+    /// out, the way git does. Without uppercase letters, everything that isn't left out is shown.
+    fn matches(&self, status: &Status) -> bool {
+        let letter = status.diff_filter_letter();
+
+        let included =
+            self.include.is_empty() || letter.is_some_and(|letter| self.include.contains(&letter));
+        let excluded = letter.is_some_and(|letter| self.exclude.contains(&letter));
+
+        included && !excluded
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ColorChoice {
+    /// Use colors if stdout is a terminal, unless `NO_COLOR` is set. Setting
+    /// `CLICOLOR_FORCE` enables colors regardless of the terminal
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto if var("NO_COLOR").is_some() => false,
+            ColorChoice::Auto if var("CLICOLOR_FORCE").is_some_and(|value| value != "0") => true,
+            ColorChoice::Auto => std::io::stdout().is_terminal(),
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -499,52 +2217,485 @@ fn fallback(path: &Path, args: &Args) -> Result<Option<Node>> {
 /// git-tree searches for a git repository the same way git does, and displays
 /// a tree showing untracked and modified files. The tree's root is the
 /// repository's root. The tree's items are colored to indicate their status
-/// (green: new, red: modified, blue: ignored). Changes to files in the index
-/// are shown in bold.
+/// (green: new, red: modified or deleted, yellow: renamed or copied, cyan:
+/// type changed, blue: ignored). Changes to files in the index are shown in
+/// bold.
 ///
 /// A column in front of each file's name indicates changes to the index and
-/// the working tree, respectively (M: modified, N: new).
-#[command(author, version, about)]
+/// the working tree, respectively (M: modified, N: new, D: deleted,
+/// R: renamed, C: copied, T: type changed, A: intent to add, !: ignored).
+/// Renamed and copied files are followed by the path they originate from and
+/// how similar they are to it.
+///
+/// In summary mode, the current branch is followed by the number of commits it
+/// is ahead (↑) and behind (↓) its upstream branch.
+///
+/// Unmerged paths are shown in purple, using the same two-letter codes as
+/// `git status --short` (e.g. UU: both modified, AA: both added, DU: deleted
+/// by us, UD: deleted by them).
+///
+/// Colors can be changed in git's config using git's color syntax, e.g.
+/// `git config tree.color.modified "bold yellow"`. The available slots are
+/// modified, added, removed, renamed, copied, typeChange, intentToAdd,
+/// stagedModified, stagedAdded, stagedRemoved, stagedRenamed, stagedCopied,
+/// conflict, ignored, marker, source, branch, insertions, deletions,
+/// filesChanged, conflicts, and error.
+#[command(author, version, about, subcommand_precedence_over_arg = true)]
 struct Args {
-    /// Include ignored files
-    #[arg(short, long)]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Include ignored files [config: tree.all]
+    #[arg(short, long, overrides_with = "no_all")]
     all: bool,
 
+    /// Don't include ignored files, even if `tree.all` is set
+    #[arg(long, overrides_with = "all")]
+    no_all: bool,
+
     /// Recursively search for repositories up to <depth> levels deep
+    /// [config: tree.depth]
     #[arg(long, default_value = "0")]
     depth: usize,
 
     /// Show only a summary containing the number of additions, deletions, and
-    /// changed files
-    #[arg(short, long)]
+    /// changed files, separately for staged and unstaged changes
+    /// [config: tree.summary]
+    #[arg(short, long, overrides_with = "no_summary")]
     summary: bool,
 
-    /// Only show repositories that contains changes (useful in combination
-    /// with --depth and --summary)
-    #[arg(long)]
+    /// Show all changed files, even if `tree.summary` is set
+    #[arg(long, overrides_with = "summary")]
+    no_summary: bool,
+
+    /// Only show repositories that contains changes or whose current branch
+    /// differs from its upstream (useful in combination with --depth and
+    /// --summary) [config: tree.onlyShowChanges]
+    #[arg(long, overrides_with = "no_only_show_changes")]
     only_show_changes: bool,
+
+    /// Show all repositories, even if `tree.onlyShowChanges` is set
+    #[arg(long, overrides_with = "only_show_changes")]
+    no_only_show_changes: bool,
+
+    /// Show the number of lines added and removed next to each file, `bin`
+    /// for binary files. Files that are both staged and modified show the sum
+    /// of both. Directories show the sum of the lines below them next to the
+    /// number of files changed, which is shown without this option as well
+    /// [config: tree.stat]
+    #[arg(long, overrides_with = "no_stat")]
+    stat: bool,
+
+    /// Don't show the number of lines per file, even if `tree.stat` is set
+    #[arg(long, overrides_with = "stat")]
+    no_stat: bool,
+
+    /// How to show untracked files, defaults to `status.showUntrackedFiles`.
+    /// Ignored directories shown with --all are always collapsed
+    #[arg(short, long, value_enum, value_name = "MODE")]
+    untracked_files: Option<ShowUntrackedFiles>,
+
+    /// Only show <DEPTH> levels of the tree below its root. Directories whose
+    /// contents are hidden show the number of changed files they contain
+    #[arg(long, global = true, value_name = "DEPTH")]
+    max_depth: Option<usize>,
+
+    /// Only show the first <N> entries of each directory, followed by the
+    /// number of entries that were left out
+    #[arg(long, global = true, value_name = "N")]
+    max_entries: Option<NonZeroUsize>,
+
+    /// Only show files with one of these statuses, e.g. `--status staged` or
+    /// `--status untracked,conflict`. With --summary, only matching files are
+    /// counted
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "STATUS"
+    )]
+    status: Vec<StatusFilter>,
+
+    /// Only show files that have been added (A), copied (C), deleted (D),
+    /// modified (M), renamed (R), have had their type changed (T), or are
+    /// unmerged (U), like `git diff --diff-filter`. Lowercase letters leave
+    /// out the corresponding files instead, e.g. `--diff-filter=d`. With
+    /// --summary, only matching files are counted
+    #[arg(long, global = true, value_name = "FILTER", value_parser = DiffFilter::parse)]
+    diff_filter: Option<DiffFilter>,
+
+    /// Merge directories that only contain a single directory into one node,
+    /// e.g. `a/b/c` [config: tree.compact]
+    #[arg(long, global = true, overrides_with = "no_compact")]
+    compact: bool,
+
+    /// Show every directory on its own, even if `tree.compact` is set
+    #[arg(long, global = true, overrides_with = "compact")]
+    no_compact: bool,
+
+    /// The output format [config: tree.format]
+    #[arg(long, global = true, value_enum, default_value = "tree")]
+    format: Format,
+
+    /// When to use colors in the tree [config: tree.color]
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto"
+    )]
+    color: ColorChoice,
+
+    /// Detect renames, both in the index and in the working tree. A file is
+    /// considered renamed if at least <PERCENT> of its content is unchanged.
+    /// Without this option, renames are only detected in the index, as
+    /// configured by `status.renames` and `diff.renames`. Unless
+    /// --untracked-files or `status.showUntrackedFiles` say otherwise, this
+    /// lists every untracked file so that they can be matched against
+    /// deleted ones
+    #[arg(short = 'M', long, global = true, value_name = "PERCENT", num_args = 0..=1, require_equals = true, default_missing_value = "50", value_parser = clap::value_parser!(u8).range(0..=100))]
+    find_renames: Option<u8>,
+
+    /// Detect copies as well as renames, see --find-renames
+    #[arg(short = 'C', long, global = true, value_name = "PERCENT", num_args = 0..=1, require_equals = true, default_missing_value = "50", value_parser = clap::value_parser!(u8).range(0..=100))]
+    find_copies: Option<u8>,
+
+    /// Do not detect renames, regardless of the git config
+    #[arg(long, global = true, conflicts_with_all = ["find_renames", "find_copies"])]
+    no_renames: bool,
+
+    /// Compare the index to <REV> instead of `HEAD`, e.g. `origin/main`, so
+    /// that the first column (and the staged changes in the summary) include
+    /// changes that have already been committed since <REV>
+    #[arg(long, value_name = "REV")]
+    against: Option<String>,
+
+    /// Repositories or directories to scan. If more than one path is given,
+    /// their trees are shown side by side under the deepest directory that
+    /// contains all of them. Paths given more than once are only shown once.
+    /// A path inside a repository shows the whole repository, labelled with
+    /// its root
+    #[arg(default_value = ".")]
+    paths: Vec<PathBuf>,
+
+    /// Exit with 1 if there are changes and 0 otherwise, like `git diff
+    /// --exit-code`. Errors always result in an exit status of 2
+    #[arg(long, global = true)]
+    exit_code: bool,
+
+    /// Abort on the first repository that cannot be read instead of showing
+    /// the error in its place
+    #[arg(long)]
+    strict: bool,
+
+    /// The number of threads used to scan repositories, defaults to the
+    /// number of available CPUs. Threads that are not busy with other
+    /// repositories help checking a repository's files for modifications
+    #[arg(short, long)]
+    jobs: Option<NonZeroUsize>,
+
+    /// Only show entries matching at least one of these pathspecs, e.g.
+    /// `git-tree -- src/ '*.rs'`. With --summary, only matching files are
+    /// counted
+    #[arg(last = true)]
+    pathspecs: Vec<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show the files that differ between two revisions, along with the number
+    /// of lines added and removed
+    ///
+    /// The first column shows how each file changed (M: modified, N: new,
+    /// D: deleted, R: renamed, C: copied).
+    Diff(DiffArgs),
+
+    /// Show the files changed by a single commit, along with the number of
+    /// lines added and removed
+    Show(ShowArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// The revision to compare from. This can also be a range, `<a>..<b>`, or
+    /// `<a>...<b>` to compare <b> to the merge base of <a> and <b>
+    from: String,
+
+    /// The revision to compare to, defaults to `HEAD`
+    to: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ShowArgs {
+    /// The commit to show
+    #[arg(default_value = "HEAD")]
+    commit: String,
+
+    /// The parent to compare a merge commit to, 1 being the first parent
+    #[arg(long, default_value = "1")]
+    parent: NonZeroUsize,
 }
 
-fn run() -> Result<()> {
-    let args = Args::parse();
+impl Args {
+    /// Takes the options that weren't given on the command line from git's config, e.g.
+    /// `tree.summary` for `--summary`.
+    fn apply_config(&mut self, matches: &ArgMatches, config: &gix::config::File<'_>) -> Result<()> {
+        let is_given = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        for (key, id, value, negated) in [
+            ("tree.all", "all", &mut self.all, self.no_all),
+            (
+                "tree.summary",
+                "summary",
+                &mut self.summary,
+                self.no_summary,
+            ),
+            (
+                "tree.onlyShowChanges",
+                "only_show_changes",
+                &mut self.only_show_changes,
+                self.no_only_show_changes,
+            ),
+            ("tree.stat", "stat", &mut self.stat, self.no_stat),
+            (
+                "tree.compact",
+                "compact",
+                &mut self.compact,
+                self.no_compact,
+            ),
+        ] {
+            if is_given(id) || negated {
+                continue;
+            }
+
+            if let Some(configured) = config.boolean(key) {
+                *value = configured.with_context(|| format!("invalid boolean in {key}"))?;
+            }
+        }
+
+        if !is_given("depth") {
+            if let Some(depth) = config.integer("tree.depth") {
+                let depth = depth.context("invalid integer in tree.depth")?;
+
+                self.depth = usize::try_from(depth).context("invalid depth in tree.depth")?;
+            }
+        }
+
+        if !is_given("format") {
+            if let Some(format) = config.string("tree.format") {
+                self.format = Format::from_str(&format.to_str_lossy(), true)
+                    .map_err(|_| anyhow!("invalid format {format:?} in tree.format"))?;
+            }
+        }
+
+        if !is_given("color") {
+            if let Some(color) = config.string("tree.color") {
+                self.color = ColorChoice::from_str(&color.to_str_lossy(), true)
+                    .map_err(|_| anyhow!("invalid color choice {color:?} in tree.color"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The pathspecs given after `--`, in the form gix's status expects.
+    fn patterns(&self) -> impl Iterator<Item = BString> + '_ {
+        self.pathspecs
+            .iter()
+            .map(|pathspec| BString::from(pathspec.as_str()))
+    }
 
-    let path = Path::new(".");
+    /// Whether `--status` or `--diff-filter` has been given.
+    fn filters_status(&self) -> bool {
+        !self.status.is_empty() || self.diff_filter.is_some()
+    }
+
+    /// Whether files with this status pass `--status` and `--diff-filter`. Ignored files are
+    /// filtered out separately unless `--all` is given.
+    fn shows(&self, status: &Status) -> bool {
+        (self.status.is_empty() || self.status.contains(&status.kind()))
+            && self
+                .diff_filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(status))
+    }
 
-    let node = match walk_path(path, args.depth, &args)? {
-        node @ Some(_) => node,
-        None => fallback(path, &args)?,
+    /// Whether ignored files are shown, either because of `--all` or `--status ignored`.
+    fn shows_ignored(&self) -> bool {
+        self.all || self.status.contains(&StatusFilter::Ignored)
+    }
+
+    fn rewrites(&self) -> Option<gix::diff::Rewrites> {
+        if self.find_renames.is_none() && self.find_copies.is_none() {
+            return None;
+        }
+
+        let percentage = |percent: u8| Some(f32::from(percent) / 100.0);
+        let default = gix::diff::Rewrites::default();
+
+        Some(gix::diff::Rewrites {
+            copies: self.find_copies.map(|percent| gix::diff::rewrites::Copies {
+                percentage: percentage(percent),
+                ..Default::default()
+            }),
+            percentage: self.find_renames.map_or(default.percentage, percentage),
+            ..default
+        })
+    }
+}
+
+/// Rewrites git's `-M50` and `-C50` to `-M=50` and `-C=50`. Both options require `=` so that a path
+/// following `-M` isn't taken as the percentage.
+fn attach_percentages(args: impl Iterator<Item = OsString>) -> Vec<OsString> {
+    let mut pathspecs = false;
+
+    args.map(|arg| {
+        pathspecs |= arg == "--";
+
+        let rewritten = arg.to_str().filter(|_| !pathspecs).and_then(|arg| {
+            let (flag, percent) = arg.split_at_checked(2)?;
+            let is_percentage = !percent.is_empty() && percent.bytes().all(|b| b.is_ascii_digit());
+
+            (matches!(flag, "-M" | "-C") && is_percentage).then(|| format!("{flag}={percent}"))
+        });
+
+        rewritten.map_or(arg, OsString::from)
+    })
+    .collect()
+}
+
+/// The exit status used for errors, chosen to not collide with the status `--exit-code` uses for
+/// changes.
+const EXIT_ERROR: u8 = 2;
+const EXIT_DIRTY: u8 = 1;
+
+fn run() -> Result<ExitCode> {
+    let matches = Args::command().get_matches_from(attach_percentages(std::env::args_os()));
+    let mut args = Args::from_arg_matches(&matches)?;
+
+    let config = config(&args.paths, args.depth)?;
+
+    args.apply_config(&matches, &config)?;
+
+    let jobs = Jobs::new(
+        args.jobs
+            .unwrap_or_else(|| thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)),
+    );
+
+    // A path that is given twice, e.g. as `a` and `./a`, would otherwise be walked twice and merged
+    // into a single entry.
+    let mut paths: Vec<&PathBuf> = Vec::with_capacity(args.paths.len());
+    let mut canonical_paths = Vec::with_capacity(args.paths.len());
+
+    for path in &args.paths {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+
+        if !canonical_paths.contains(&canonical) {
+            paths.push(path);
+            canonical_paths.push(canonical);
+        }
+    }
+
+    let mut root = match (&args.command, paths.as_slice()) {
+        (Some(Command::Diff(diff)), _) => {
+            let repo = gix::discover(".").context("no git repository found")?;
+
+            walk_diff(&repo, diff, &args)?
+        }
+        (Some(Command::Show(show)), _) => {
+            let repo = gix::discover(".").context("no git repository found")?;
+
+            walk_show(&repo, show, &args)?
+        }
+        (None, [path]) => match walk_root(path, path, &args, &jobs)? {
+            Some(node) => node,
+            // The repository was found, but it was filtered out by `--only-show-changes`.
+            None => return Ok(ExitCode::SUCCESS),
+        },
+        (None, paths) => {
+            let (common_root, names) = common_root(paths)?;
+
+            let mut root = Tree {
+                name: to_bstring(common_root.as_os_str()),
+                children: BTreeMap::new(),
+            };
+
+            let entries: Vec<_> = paths.iter().copied().zip(names).collect();
+            let results = jobs.map(&entries, |(path, name)| walk_root(path, name, &args, &jobs));
+
+            for ((path, name), result) in entries.iter().zip(results) {
+                match result {
+                    Ok(Some(node)) => root.add_node(node, to_bstring(name.as_os_str())),
+                    Ok(None) => {}
+                    Err(err) => root.add_node(
+                        failure(path, name.as_os_str(), err, &args)?,
+                        to_bstring(name.as_os_str()),
+                    ),
+                }
+            }
+
+            Node::Tree(root)
+        }
     };
 
-    match node {
-        Some(root) => println!("{}", root.to_tree()),
-        _ => println!("no git repository found at {:?}", path),
+    if args.compact {
+        if let Node::Tree(tree) = &mut root {
+            tree.compact();
+        }
     }
 
-    Ok(())
+    match args.format {
+        Format::Tree => {
+            let theme = if args.color.enabled() {
+                Theme::from_config(&config)?
+            } else {
+                Theme::default()
+            };
+
+            let limits = Limits {
+                depth: args.max_depth,
+                entries: args.max_entries,
+            };
+
+            println!("{}", root.to_tree(&theme, limits))
+        }
+        Format::Json => {
+            let document = json!({
+                "version": JSON_SCHEMA_VERSION,
+                "root": root.to_json(""),
+            });
+
+            println!("{}", serde_json::to_string_pretty(&document)?);
+        }
+        Format::Ndjson => {
+            let mut records = Vec::new();
+
+            root.to_json_records("", &mut records);
+
+            for record in records {
+                println!("{}", serde_json::to_string(&record)?);
+            }
+        }
+    }
+
+    if root.has_failures() {
+        Ok(ExitCode::from(EXIT_ERROR))
+    } else if args.exit_code && root.is_dirty() {
+        Ok(ExitCode::from(EXIT_DIRTY))
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
 }
 
-fn main() {
-    if let Err(err) = run() {
-        println!("{}", err);
+fn main() -> ExitCode {
+    match run() {
+        Ok(exit_code) => exit_code,
+        Err(err) => {
+            eprintln!("{}", err);
+
+            ExitCode::from(EXIT_ERROR)
+        }
     }
 }
@@ -0,0 +1,50 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(against_tag, "against", vec!["--against", "base"]);
+mktest!(
+    against_tag_summary,
+    "against",
+    vec!["--against", "base", "--summary"]
+);
+mktest!(
+    against_parent_summary,
+    "against",
+    vec!["--against", "HEAD~1", "--summary"]
+);
+mktest!(against_head, "against", vec!["--against", "HEAD"]);
+
+#[test]
+fn against_unknown_revision() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("against.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["--against", "does-not-exist"])
+        .assert()
+        .code(2)
+        .stdout_eq("")
+        .stderr_eq(snapbox::file![_: TermSvg]);
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr, $env:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            let mut command = Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .env_remove("NO_COLOR")
+                .env_remove("CLICOLOR_FORCE")
+                .args($args);
+
+            let env: &[(&str, &str)] = $env;
+
+            for (key, value) in env {
+                command = command.env(key, value);
+            }
+
+            command
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(never, "some_staged_changes", vec!["--color", "never"], &[]);
+mktest!(
+    never_summary,
+    "some_staged_changes",
+    vec!["--color", "never", "--summary"],
+    &[]
+);
+mktest!(
+    auto_without_terminal,
+    "some_staged_changes",
+    Vec::<&str>::new(),
+    &[]
+);
+mktest!(
+    clicolor_force,
+    "some_staged_changes",
+    Vec::<&str>::new(),
+    &[("CLICOLOR_FORCE", "1")]
+);
+mktest!(
+    no_color,
+    "some_staged_changes",
+    Vec::<&str>::new(),
+    &[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]
+);
+mktest!(
+    always_with_no_color,
+    "some_staged_changes",
+    vec!["--color", "always"],
+    &[("NO_COLOR", "1")]
+);
+mktest!(theme, "theme", vec!["--color", "always"], &[]);
+mktest!(
+    theme_summary,
+    "theme",
+    vec!["--color", "always", "--summary"],
+    &[]
+);
+
+#[test]
+fn invalid_theme() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("invalid_theme.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["--color", "always"])
+        .assert()
+        .code(2)
+        .stdout_eq("")
+        .stderr_eq(snapbox::file![_: TermSvg]);
+
+    Ok(())
+}
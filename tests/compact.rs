@@ -0,0 +1,42 @@
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(compact, "some_staged_changes", vec!["--compact"]);
+mktest!(compact_stat, "stat", vec!["--compact", "--stat"]);
+mktest!(
+    compact_diff,
+    "diff",
+    vec!["diff", "base", "feature", "--compact"]
+);
+
+#[test]
+fn compact_json() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("some_staged_changes.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["--compact", "--format", "json"])
+        .assert()
+        .success()
+        .stdout_eq(snapbox::file![_: Json]);
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr, $config:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            let config: Vec<(&str, &str)> = $config;
+
+            let mut command = Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .env("GIT_CONFIG_COUNT", config.len().to_string());
+
+            for (index, (key, value)) in config.into_iter().enumerate() {
+                command = command
+                    .env(format!("GIT_CONFIG_KEY_{index}"), key)
+                    .env(format!("GIT_CONFIG_VALUE_{index}"), value);
+            }
+
+            command
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(summary, "config_defaults", Vec::<&str>::new(), vec![]);
+mktest!(no_summary, "config_defaults", vec!["--no-summary"], vec![]);
+mktest!(
+    depth_summary,
+    "some_changes_depth",
+    Vec::<&str>::new(),
+    vec![("tree.depth", "1"), ("tree.summary", "true")]
+);
+mktest!(
+    compact,
+    "some_staged_changes",
+    Vec::<&str>::new(),
+    vec![("tree.compact", "true")]
+);
+mktest!(
+    no_compact,
+    "some_staged_changes",
+    vec!["--no-compact"],
+    vec![("tree.compact", "true")]
+);
+
+#[test]
+fn invalid_format() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("some_changes.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .env("GIT_CONFIG_COUNT", "1")
+        .env("GIT_CONFIG_KEY_0", "tree.format")
+        .env("GIT_CONFIG_VALUE_0", "xml")
+        .assert()
+        .code(2)
+        .stdout_eq("")
+        .stderr_eq(snapbox::file![_: TermSvg]);
+
+    Ok(())
+}
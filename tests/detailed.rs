@@ -3,13 +3,15 @@ use std::env;
 use snapbox::cmd::Command;
 
 macro_rules! mktest {
-    ($name:ident, $case:expr) => {
+    ($(#[$attr:meta])* $name:ident, $case:expr) => {
         #[test]
+        $(#[$attr])*
         fn $name() -> gix_testtools::Result {
             let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
 
             Command::new(snapbox::cmd::cargo_bin!("git-tree"))
                 .current_dir(path)
+                .args(["--color", "always"])
                 .assert()
                 .success()
                 .stdout_eq(snapbox::file![_: TermSvg]);
@@ -23,3 +25,12 @@ mktest!(no_changes, "no_changes");
 mktest!(some_changes, "some_changes");
 mktest!(some_staged_changes, "some_staged_changes");
 mktest!(additions_deletions, "additions_deletions");
+mktest!(merge_conflicts, "merge_conflicts");
+mktest!(odd_names, "odd_names");
+mktest!(replaced_paths, "replaced_paths");
+// APFS and NTFS reject file names that aren't valid UTF-8.
+mktest!(
+    #[cfg(all(unix, not(target_os = "macos")))]
+    unusual_names,
+    "unusual_names"
+);
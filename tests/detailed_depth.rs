@@ -10,6 +10,7 @@ macro_rules! mktest {
 
             Command::new(snapbox::cmd::cargo_bin!("git-tree"))
                 .current_dir(path)
+                .args(["--color", "always"])
                 .args($args)
                 .assert()
                 .success()
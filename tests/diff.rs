@@ -0,0 +1,65 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(two_revisions, "diff", vec!["diff", "base", "feature"]);
+mktest!(range, "diff", vec!["diff", "main..feature"]);
+mktest!(merge_base, "diff", vec!["diff", "main...feature"]);
+mktest!(
+    single_revision,
+    "diff",
+    vec!["diff", "feature", "--find-renames=90"]
+);
+mktest!(
+    no_renames,
+    "diff",
+    vec!["diff", "main..feature", "--no-renames"]
+);
+
+#[test]
+fn range_json() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("diff.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["diff", "main..feature", "--format", "json"])
+        .assert()
+        .success()
+        .stdout_eq(snapbox::file![_: Json]);
+
+    Ok(())
+}
+
+#[test]
+fn unknown_revision() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("diff.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["diff", "does-not-exist"])
+        .assert()
+        .code(2)
+        .stdout_eq("")
+        .stderr_eq(snapbox::file![_: TermSvg]);
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .code(2)
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(errors_depth, "errors_depth", vec!["--depth", "1"]);
+mktest!(
+    errors_summary_depth,
+    "errors_depth",
+    vec!["--summary", "--depth", "1"]
+);
+
+#[test]
+fn errors_json_depth() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("errors_depth.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["--format", "json", "--depth", "1"])
+        .assert()
+        .code(2)
+        .stdout_eq(snapbox::file![_: Json]);
+
+    Ok(())
+}
+
+#[test]
+fn errors_strict_depth() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("errors_depth.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["--strict", "--depth", "1"])
+        .assert()
+        .code(2)
+        .stdout_eq("")
+        .stderr_eq(snapbox::file![_: TermSvg]);
+
+    Ok(())
+}
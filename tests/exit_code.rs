@@ -0,0 +1,92 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($(#[$attr:meta])* $name:ident, $case:expr, $args:expr, $code:expr) => {
+        #[test]
+        $(#[$attr])*
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args($args)
+                .assert()
+                .code($code);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(no_changes, "no_changes", vec!["--exit-code"], 0);
+mktest!(some_changes, "some_changes", vec!["--exit-code"], 1);
+mktest!(
+    some_changes_summary,
+    "some_changes",
+    vec!["--exit-code", "--summary"],
+    1
+);
+mktest!(
+    some_changes_without_exit_code,
+    "some_changes",
+    Vec::<&str>::new(),
+    0
+);
+mktest!(
+    no_changes_depth,
+    "no_changes_depth",
+    vec!["--exit-code", "--depth", "1"],
+    0
+);
+mktest!(
+    some_staged_changes_depth,
+    "some_staged_changes_depth",
+    vec!["--exit-code", "--summary", "--depth", "1"],
+    1
+);
+mktest!(
+    pathspec_without_matches,
+    "some_changes",
+    vec!["--exit-code", "--", "does-not-exist"],
+    0
+);
+mktest!(
+    no_changes_only_show_changes,
+    "no_changes",
+    vec!["--exit-code", "--summary", "--only-show-changes"],
+    0
+);
+mktest!(
+    diff_with_changes,
+    "diff",
+    vec!["diff", "base", "feature", "--exit-code"],
+    1
+);
+mktest!(
+    diff_without_changes,
+    "diff",
+    vec!["diff", "feature", "feature", "--exit-code"],
+    0
+);
+mktest!(
+    staged_rename_summary,
+    "changes_without_lines_depth",
+    vec!["--exit-code", "--summary", "staged_rename"],
+    1
+);
+mktest!(
+    staged_binary_summary,
+    "changes_without_lines_depth",
+    vec!["--exit-code", "--summary", "staged_binary"],
+    1
+);
+// Windows has no executable bit.
+mktest!(
+    #[cfg(unix)]
+    mode_change_summary,
+    "changes_without_lines_depth",
+    vec!["--exit-code", "--summary", "mode_change"],
+    1
+);
@@ -0,0 +1,25 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+seq 1 10 >> 1.txt
+seq 1 10 >> 2.txt
+git add .
+git commit -q -m c1
+git tag base
+
+mkdir a
+seq 21 30 >> a/3.txt
+seq 11 15 >> 1.txt
+git add .
+git commit -q -m c2
+
+git rm -q 2.txt
+git commit -q -m c3
+
+seq 16 20 >> 1.txt
+git add 1.txt
+
+seq 11 20 >> a/3.txt
+seq 1 5 >> 4.txt
@@ -0,0 +1,30 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q staged_rename
+(cd staged_rename
+  seq 1 10 >> 1.txt
+  git add 1.txt
+  git commit -q -m c1
+
+  git mv 1.txt 2.txt
+)
+
+git init -q staged_binary
+(cd staged_binary
+  printf 'binary\0content' > image.bin
+  git add image.bin
+  git commit -q -m c1
+
+  printf 'binary\0changed' > image.bin
+  git add image.bin
+)
+
+git init -q mode_change
+(cd mode_change
+  seq 1 10 >> script.sh
+  git add script.sh
+  git commit -q -m c1
+
+  chmod +x script.sh
+)
@@ -0,0 +1,20 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q readable
+(cd readable
+  seq 1 10 >> 1.txt
+  git add 1.txt
+  git commit -q -m c1
+
+  seq 11 20 >> 1.txt
+)
+
+git init -q corrupt_ref
+(cd corrupt_ref
+  seq 1 10 >> 1.txt
+  git add 1.txt
+  git commit -q -m c1
+
+  printf "not an object id" > .git/refs/heads/main
+)
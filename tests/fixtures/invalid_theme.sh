@@ -0,0 +1,10 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+seq 1 10 >> 1.txt
+git add 1.txt
+git commit -q -m c1
+
+git config tree.color.modified "not-a-color"
@@ -0,0 +1,29 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+echo base >> both_modified.txt
+echo base >> deleted_by_them.txt
+echo base >> deleted_by_us.txt
+git add .
+git commit -q -m base
+
+git checkout -q -b theirs
+echo theirs >> both_modified.txt
+git rm -q deleted_by_them.txt
+echo theirs >> deleted_by_us.txt
+git add .
+git commit -q -m theirs
+
+git checkout -q -
+echo ours >> both_modified.txt
+echo ours >> deleted_by_them.txt
+git rm -q deleted_by_us.txt
+git add .
+git commit -q -m ours
+
+git merge -q theirs >/dev/null || true
+
+echo resolved > both_modified.txt
+echo new >> new.txt
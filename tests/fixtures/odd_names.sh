@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git config core.quotePath false
+
+mkdir 'ünïcödé'
+seq 1 10 >> 'ünïcödé/1.txt'
+seq 1 10 >> '-leading-dash.txt'
+seq 1 10 >> 'with space.txt'
+git add .
+git commit -q -m c1
+
+seq 11 20 >> 'ünïcödé/1.txt'
+seq 1 10 >> 'ünïcödé/日本語.txt'
+seq 11 20 >> '-leading-dash.txt'
+git add -- '-leading-dash.txt'
+seq 11 20 >> 'with space.txt'
@@ -0,0 +1,19 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+seq 1 10 >> 1.txt
+seq 11 20 >> 2.txt
+mkdir -p a/b
+seq 21 30 >> a/b/3.txt
+git add 1.txt 2.txt a/b/3.txt
+git commit -q -m c1
+
+git mv 1.txt renamed.txt
+
+git mv 2.txt a/moved.txt
+seq 31 32 >> a/moved.txt
+git add a/moved.txt
+
+mv a/b/3.txt a/4.txt
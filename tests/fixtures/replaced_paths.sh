@@ -0,0 +1,24 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+mkdir -p directory_replaced_by_file
+seq 1 10 >> directory_replaced_by_file/1.txt
+seq 1 10 >> directory_replaced_by_file/2.txt
+seq 1 10 >> file_replaced_by_directory
+mkdir partially_replaced
+seq 1 10 >> partially_replaced/3.txt
+git add .
+git commit -q -m c1
+
+rm -r directory_replaced_by_file
+seq 1 10 >> directory_replaced_by_file
+
+rm file_replaced_by_directory
+mkdir file_replaced_by_directory
+seq 1 10 >> file_replaced_by_directory/4.txt
+
+rm partially_replaced/3.txt
+mkdir partially_replaced/3.txt
+seq 1 10 >> partially_replaced/3.txt/5.txt
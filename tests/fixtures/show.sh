@@ -0,0 +1,32 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+seq 1 10 >> 1.txt
+seq 1 10 >> 2.txt
+mkdir -p a/b
+seq 1 20 >> a/b/3.txt
+git add .
+git commit -q -m c1
+git tag base
+
+git checkout -q -b feature
+
+seq 11 15 >> 1.txt
+git rm -q 2.txt
+git mv a/b/3.txt a/b/4.txt
+echo 21 >> a/b/4.txt
+mkdir c
+seq 1 5 >> c/5.txt
+git add .
+git commit -q -m c2
+
+git checkout -q main
+
+seq 1 3 >> 6.txt
+sed -i.bak 1d 1.txt && rm 1.txt.bak
+git add .
+git commit -q -m c3
+
+git merge -q --no-edit -m "Merge feature" feature
@@ -0,0 +1,28 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+seq 1 10 >> 1.txt
+seq 1 10 >> 2.txt
+mkdir -p a/b
+seq 1 20 >> a/b/3.txt
+printf 'binary\0content' > image.bin
+git add .
+git commit -q -m c1
+
+seq 11 15 >> 1.txt
+git add 1.txt
+seq 16 17 >> 1.txt
+
+sed -i.bak 1,3d 2.txt && rm 2.txt.bak
+
+seq 21 24 >> a/b/3.txt
+git add a/b/3.txt
+
+printf 'binary\0changed' > image.bin
+
+seq 1 6 >> 4.txt
+
+mkdir c
+seq 1 3 >> c/5.txt
@@ -0,0 +1,33 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+seq 1 10 >> 1.txt
+seq 1 10 >> 2.txt
+seq 1 10 >> 3.txt
+ln -s 1.txt 4.txt
+git add .
+git commit -q -m c1
+
+seq 11 20 >> 1.txt
+git add 1.txt
+
+seq 11 20 >> 2.txt
+rm 3.txt
+
+rm 4.txt
+seq 1 10 >> 4.txt
+
+seq 1 10 >> 5.txt
+git add --intent-to-add 5.txt
+
+git config tree.color.stagedModified "bold blue"
+git config tree.color.modified cyan
+git config tree.color.removed "magenta"
+git config tree.color.intentToAdd 208
+git config tree.color.typeChange "bold green"
+git config tree.color.marker yellow
+git config tree.color.branch "bold 33"
+git config tree.color.insertions blue
+git config tree.color.deletions "bold red"
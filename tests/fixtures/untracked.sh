@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+printf 'build\n*.log\n' > .gitignore
+seq 1 10 >> 1.txt
+git add .
+git commit -q -m c1
+
+seq 11 15 >> 1.txt
+
+mkdir -p new/a/b
+seq 1 3 >> new/2.txt
+seq 1 3 >> new/a/3.txt
+seq 1 3 >> new/a/b/4.txt
+
+mkdir -p build/debug
+seq 1 3 >> build/out.o
+seq 1 3 >> build/debug/out.o
+seq 1 3 >> build/debug/out.d
+
+seq 1 3 >> debug.log
@@ -0,0 +1,15 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+printf '*.o\n' > .gitignore
+seq 1 10 >> 1.txt
+git add .
+git commit -q -m c1
+
+mkdir -p build/obj
+seq 1 3 >> build/a.c
+seq 1 3 >> build/obj/1.o
+seq 1 3 >> build/obj/2.o
+seq 1 3 >> build/obj/3.o
@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git config core.quotePath false
+
+mkdir $'latin1-\xe9'
+seq 1 10 >> $'latin1-\xe9/1.txt'
+seq 1 10 >> $'\xff.txt'
+seq 1 10 >> 'with space.txt'
+git add .
+git commit -q -m c1
+
+seq 11 20 >> $'latin1-\xe9/1.txt'
+seq 1 10 >> $'latin1-\xe9/\xfe new.txt'
+seq 11 20 >> $'\xff.txt'
+git add $'\xff.txt'
+seq 11 20 >> 'with space.txt'
@@ -0,0 +1,30 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q upstream
+(cd upstream
+  seq 1 10 >> 1.txt
+  git add 1.txt
+  git commit -q -m c1
+)
+
+git clone -q upstream ahead_behind
+git clone -q upstream in_sync
+
+(cd ahead_behind
+  seq 11 20 >> 1.txt
+  git commit -q -am c2
+
+  seq 21 30 >> 1.txt
+  git commit -q -am c3
+)
+
+(cd upstream
+  seq 1 10 >> 2.txt
+  git add 2.txt
+  git commit -q -m c4
+)
+
+(cd ahead_behind
+  git fetch -q
+)
@@ -0,0 +1,54 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: Json]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(no_changes, "no_changes", vec!["--format", "json"]);
+mktest!(
+    some_staged_changes,
+    "some_staged_changes",
+    vec!["--format", "json"]
+);
+mktest!(
+    additions_deletions,
+    "additions_deletions",
+    vec!["--format", "json"]
+);
+mktest!(
+    some_changes_depth,
+    "some_changes_depth",
+    vec!["--format", "json", "--depth", "1"]
+);
+mktest!(
+    additions_deletions_summary_depth,
+    "additions_deletions_depth",
+    vec!["--format", "json", "--summary", "--depth", "1"]
+);
+mktest!(
+    renames,
+    "renames",
+    vec!["--format", "json", "--find-renames"]
+);
+mktest!(merge_conflicts, "merge_conflicts", vec!["--format", "json"]);
+mktest!(
+    against_summary,
+    "against",
+    vec!["--format", "json", "--summary", "--against", "base"]
+);
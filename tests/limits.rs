@@ -0,0 +1,43 @@
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(max_depth, "some_staged_changes", vec!["--max-depth", "1"]);
+mktest!(
+    max_depth_root,
+    "some_staged_changes",
+    vec!["--max-depth", "0"]
+);
+mktest!(max_depth_stat, "stat", vec!["--max-depth", "1", "--stat"]);
+mktest!(
+    max_entries,
+    "some_staged_changes",
+    vec!["--max-entries", "2"]
+);
+mktest!(
+    max_depth_max_entries,
+    "some_changes_depth",
+    vec!["--depth", "1", "--max-depth", "1", "--max-entries", "1"]
+);
+mktest!(
+    max_entries_diff,
+    "diff",
+    vec!["diff", "base", "feature", "--max-entries", "3"]
+);
@@ -0,0 +1,48 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($(#[$attr:meta])* $name:ident, $case:expr, $args:expr) => {
+        #[test]
+        $(#[$attr])*
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: JsonLines]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(some_changes, "some_changes", vec!["--format", "ndjson"]);
+mktest!(
+    some_changes_summary,
+    "some_changes",
+    vec!["--format", "ndjson", "--summary"]
+);
+mktest!(
+    additions_deletions_summary_depth,
+    "additions_deletions_depth",
+    vec!["--format", "ndjson", "--summary", "--depth", "1"]
+);
+mktest!(
+    upstream_summary_depth,
+    "upstream_depth",
+    vec!["--format", "ndjson", "--summary", "--depth", "1"]
+);
+mktest!(odd_names, "odd_names", vec!["--format", "ndjson"]);
+mktest!(replaced_paths, "replaced_paths", vec!["--format", "ndjson"]);
+// APFS and NTFS reject file names that aren't valid UTF-8.
+mktest!(
+    #[cfg(all(unix, not(target_os = "macos")))]
+    unusual_names,
+    "unusual_names",
+    vec!["--format", "ndjson"]
+);
@@ -8,8 +8,9 @@ fn no_git_repo() -> gix_testtools::Result {
     Command::new(snapbox::cmd::cargo_bin!("git-tree"))
         .current_dir(empty_dir.path())
         .assert()
-        .success()
-        .stdout_eq(snapbox::file![_: TermSvg]);
+        .code(2)
+        .stdout_eq("")
+        .stderr_eq(snapbox::file![_: TermSvg]);
 
     Ok(())
 }
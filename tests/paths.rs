@@ -0,0 +1,50 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(single_path, "additions_deletions_depth", vec!["first"]);
+mktest!(
+    multiple_paths,
+    "additions_deletions_depth",
+    vec!["first", "second"]
+);
+mktest!(
+    multiple_paths_summary,
+    "additions_deletions_depth",
+    vec!["--summary", "first", "second"]
+);
+mktest!(
+    duplicate_paths,
+    "additions_deletions_depth",
+    vec!["first", "second", "first/", "./second"]
+);
+mktest!(
+    dotted_paths,
+    "additions_deletions_depth",
+    vec!["./first", "second/"]
+);
+mktest!(path_inside_repository, "some_staged_changes", vec!["a/b"]);
+mktest!(
+    multiple_paths_single_job,
+    "additions_deletions_depth",
+    vec!["--jobs", "1", "first", "second"]
+);
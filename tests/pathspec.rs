@@ -0,0 +1,31 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(directory, "some_staged_changes", vec!["--", "a/"]);
+mktest!(files, "some_staged_changes", vec!["--", "1.txt", "5.txt"]);
+mktest!(glob, "some_staged_changes", vec!["--", "a/b/c/4*"]);
+mktest!(
+    summary,
+    "some_staged_changes",
+    vec!["--summary", "--", "a/"]
+);
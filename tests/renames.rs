@@ -0,0 +1,28 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(renames, "renames", Vec::<&str>::new());
+mktest!(find_renames, "renames", vec!["--find-renames"]);
+mktest!(find_renames_percentage, "renames", vec!["-M90"]);
+mktest!(find_renames_path, "renames", vec!["-M", "."]);
+mktest!(no_renames, "renames", vec!["--no-renames"]);
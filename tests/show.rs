@@ -0,0 +1,46 @@
+use std::env;
+
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(merge_commit, "show", vec!["show"]);
+mktest!(
+    merge_commit_second_parent,
+    "show",
+    vec!["show", "--parent", "2"]
+);
+mktest!(commit, "show", vec!["show", "HEAD^2"]);
+mktest!(root_commit, "show", vec!["show", "base"]);
+
+#[test]
+fn missing_parent() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("show.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["show", "--parent", "3"])
+        .assert()
+        .code(2)
+        .stdout_eq("")
+        .stderr_eq(snapbox::file![_: TermSvg]);
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(stat, "stat", vec!["--stat"]);
+mktest!(
+    stat_untracked_files,
+    "stat",
+    vec!["--stat", "--find-renames"]
+);
+
+#[test]
+fn stat_json() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("stat.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["--stat", "--format", "json"])
+        .assert()
+        .success()
+        .stdout_eq(snapbox::file![_: Json]);
+
+    Ok(())
+}
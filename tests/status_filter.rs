@@ -0,0 +1,61 @@
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(staged, "stat", vec!["--status", "staged"]);
+mktest!(
+    untracked_unstaged,
+    "stat",
+    vec!["--status", "untracked,unstaged"]
+);
+mktest!(conflict, "merge_conflicts", vec!["--status", "conflict"]);
+mktest!(ignored, "untracked", vec!["--status", "ignored"]);
+mktest!(diff_filter, "some_staged_changes", vec!["--diff-filter=A"]);
+mktest!(diff_filter_exclude, "stat", vec!["--diff-filter=m"]);
+mktest!(
+    diff_filter_diff,
+    "diff",
+    vec!["diff", "base", "feature", "--diff-filter=RD"]
+);
+
+#[test]
+fn unknown_diff_filter() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("stat.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["--diff-filter=Q"])
+        .assert()
+        .code(2)
+        .stdout_eq("")
+        .stderr_eq(snapbox::file![_: TermSvg]);
+
+    Ok(())
+}
+mktest!(
+    staged_summary,
+    "some_staged_changes",
+    vec!["--summary", "--status", "staged"]
+);
+mktest!(
+    diff_filter_summary,
+    "some_staged_changes",
+    vec!["--summary", "--diff-filter=A"]
+);
@@ -10,6 +10,7 @@ macro_rules! mktest {
 
             Command::new(snapbox::cmd::cargo_bin!("git-tree"))
                 .current_dir(path)
+                .args(["--color", "always"])
                 .args($args)
                 .assert()
                 .success()
@@ -32,3 +33,5 @@ mktest!(
     "additions_deletions",
     vec!["--summary"]
 );
+mktest!(merge_conflicts, "merge_conflicts", vec!["--summary"]);
+mktest!(renames, "renames", vec!["--summary"]);
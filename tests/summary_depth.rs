@@ -3,13 +3,15 @@ use std::env;
 use snapbox::cmd::Command;
 
 macro_rules! mktest {
-    ($name:ident, $case:expr, $args:expr) => {
+    ($(#[$attr:meta])* $name:ident, $case:expr, $args:expr) => {
         #[test]
+        $(#[$attr])*
         fn $name() -> gix_testtools::Result {
             let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
 
             Command::new(snapbox::cmd::cargo_bin!("git-tree"))
                 .current_dir(path)
+                .args(["--color", "always"])
                 .args($args)
                 .assert()
                 .success()
@@ -40,3 +42,25 @@ mktest!(
     "additions_deletions_depth",
     vec!["--summary", "--depth", "1"]
 );
+mktest!(
+    upstream_depth,
+    "upstream_depth",
+    vec!["--summary", "--depth", "1"]
+);
+mktest!(
+    upstream_depth_only_show_changes,
+    "upstream_depth",
+    vec!["--summary", "--depth", "1", "--only-show-changes"]
+);
+mktest!(
+    some_changes_depth_jobs,
+    "some_changes_depth",
+    vec!["--summary", "--depth", "1", "--jobs", "4"]
+);
+// Windows has no executable bit.
+mktest!(
+    #[cfg(unix)]
+    changes_without_lines_depth_only_show_changes,
+    "changes_without_lines_depth",
+    vec!["--summary", "--depth", "1", "--only-show-changes"]
+);
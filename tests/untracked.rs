@@ -0,0 +1,93 @@
+use snapbox::cmd::Command;
+
+macro_rules! mktest {
+    ($name:ident, $case:expr, $args:expr) => {
+        #[test]
+        fn $name() -> gix_testtools::Result {
+            let path = gix_testtools::scripted_fixture_read_only(format!("{}.sh", $case))?;
+
+            Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+                .current_dir(path)
+                .args(["--color", "always"])
+                .args($args)
+                .assert()
+                .success()
+                .stdout_eq(snapbox::file![_: TermSvg]);
+
+            Ok(())
+        }
+    };
+}
+
+mktest!(untracked_default, "untracked", Vec::<&str>::new());
+mktest!(
+    untracked_normal,
+    "untracked",
+    vec!["--untracked-files", "normal"]
+);
+mktest!(untracked_all, "untracked", vec!["--untracked-files", "all"]);
+mktest!(untracked_no, "untracked", vec!["--untracked-files", "no"]);
+mktest!(ignored, "untracked", vec!["--all"]);
+mktest!(
+    ignored_untracked_all,
+    "untracked",
+    vec!["--all", "-u", "all"]
+);
+mktest!(
+    ignored_inside_untracked,
+    "untracked_with_ignored",
+    vec!["--all"]
+);
+mktest!(
+    find_renames_untracked_normal,
+    "untracked",
+    vec!["--find-renames", "-u", "normal"]
+);
+
+#[test]
+fn untracked_from_config() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("untracked.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["--color", "always"])
+        .env("GIT_CONFIG_COUNT", "1")
+        .env("GIT_CONFIG_KEY_0", "status.showUntrackedFiles")
+        .env("GIT_CONFIG_VALUE_0", "all")
+        .assert()
+        .success()
+        .stdout_eq(snapbox::file![_: TermSvg]);
+
+    Ok(())
+}
+
+#[test]
+fn find_renames_untracked_from_config() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("untracked.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["--color", "always", "--find-renames"])
+        .env("GIT_CONFIG_COUNT", "1")
+        .env("GIT_CONFIG_KEY_0", "status.showUntrackedFiles")
+        .env("GIT_CONFIG_VALUE_0", "normal")
+        .assert()
+        .success()
+        .stdout_eq(snapbox::file![_: TermSvg]);
+
+    Ok(())
+}
+
+#[test]
+fn untracked_json() -> gix_testtools::Result {
+    let path = gix_testtools::scripted_fixture_read_only("untracked.sh")?;
+
+    Command::new(snapbox::cmd::cargo_bin!("git-tree"))
+        .current_dir(path)
+        .args(["--all", "--format", "json"])
+        .assert()
+        .success()
+        .stdout_eq(snapbox::file![_: Json]);
+
+    Ok(())
+}